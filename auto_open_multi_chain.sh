@@ -7,7 +7,6 @@
 
 DEFAULT_CHAIN_WALLET_0="e476187f6ddfeb9d588c7b45d3df334d5501d6499b3f9ad5595cae86cce16a65"
 DEFAULT_CHAIN_WALLET_1="1db1936dad0717597a7743a8353c9c0191c14c3a129b258e9743aec2b4f05d03"
-RESET_PASSWORD="L1vzziYAJAiQD6qMQZRFDkf3rADj5g"
 
 linera -w0 transfer --from $DEFAULT_CHAIN_WALLET_0 --to $DEFAULT_CHAIN_WALLET_1 100000000
 linera -w0 sync && linera -w0 query-balance
@@ -56,10 +55,15 @@ deploy_black_jack_app() {
   linera -w1 --wait-for-outgoing-messages project publish-and-create black_jack_chain \
   --json-parameters "{
   \"leaderboard_chain_id\": \"$DEFAULT_CHAIN_WALLET_1\",
-  \"leaderboard_pass\": \"$RESET_PASSWORD\",
   \"room_status_chain_id\": \"$ROOM_STATUS_CHAIN_ID\",
   \"analytics_chain_id\": \"$ANALYTICS_CHAIN_ID\",
   \"player_status_chain_id\": \"$PLAYER_STATUS_CHAIN_ID\"
+  }" \
+  --json-argument "{
+  \"admins\": [],
+  \"shoe\": { \"decks\": 6, \"penetration\": 75 },
+  \"timeouts\": { \"lobby\": 18000000, \"turn\": 10000000 },
+  \"matchmaking\": { \"band\": 1000, \"widening\": 100 }
   }"
   if [ $? -ne 0 ]; then
       echo "publish-and-create BlackJack app failed. Exiting..."
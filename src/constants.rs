@@ -16,7 +16,60 @@ pub const CARD_DECKS: [u8; 52] = [
     40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52
 ];
 
+/// A shoe holds 1 to 8 decks and is reshuffled once the dealt share of it reaches the
+/// penetration percentage, a shoe played until empty is refilled on the next draw.
+pub const MIN_SHOE_DECKS: u8 = 1;
+pub const MAX_SHOE_DECKS: u8 = 8;
+pub const DEFAULT_SHOE_DECKS: u8 = 1;
+pub const MIN_SHOE_PENETRATION: u8 = 1;
+pub const MAX_SHOE_PENETRATION: u8 = 100;
+pub const DEFAULT_SHOE_PENETRATION: u8 = 75;
+
 /// ------------------------------------------------------------------------------------------
 pub const MILLENNIUM: u64 = 946684800000000;
 pub const UNIX_MICRO_IN_18_SECONDS: u64 = 18_000_000;
-pub const UNIX_MICRO_IN_10_SECONDS: u64 = 10_000_000;
\ No newline at end of file
+pub const UNIX_MICRO_IN_10_SECONDS: u64 = 10_000_000;
+
+/// Lobby timeout resets a waiting table or lets new players take over a stalled game,
+/// turn timeout is how long a player can idle before the idle action check stands for them.
+/// Both are in microseconds and the lobby timeout must be longer than the turn timeout.
+pub const MIN_TURN_TIMEOUT: u64 = 5_000_000;
+pub const MAX_TURN_TIMEOUT: u64 = 300_000_000;
+pub const DEFAULT_TURN_TIMEOUT: u64 = UNIX_MICRO_IN_10_SECONDS;
+pub const MIN_LOBBY_TIMEOUT: u64 = 10_000_000;
+pub const MAX_LOBBY_TIMEOUT: u64 = 600_000_000;
+pub const DEFAULT_LOBBY_TIMEOUT: u64 = UNIX_MICRO_IN_18_SECONDS;
+/// ------------------------------------------------------------------------------------------
+/// House hand used in [GameMode::Dealer](crate::GameMode::Dealer)
+///
+/// The dealer keeps drawing while its score is below DEALER_STAND_THRESHOLD.
+pub const DEALER_ID: &str = "dealer";
+pub const DEALER_NAME: &str = "Dealer";
+pub const DEALER_GID: &str = "dealer";
+pub const DEALER_STAND_THRESHOLD: u8 = 17;
+
+/// A table seats 2 (head-to-head) to 7 players, chosen by the player who opens it
+pub const MIN_TABLE_SEATS: u8 = 2;
+pub const MAX_TABLE_SEATS: u8 = 7;
+pub const DEFAULT_TABLE_SEATS: u8 = 2;
+
+/// Elo rating given to a new player on the leaderboard, and the most a rating moves per game
+pub const DEFAULT_RATING: u32 = 1500;
+pub const RATING_K_FACTOR: f64 = 32.0;
+
+/// Matchmaking drops a queued player and releases a reserved seat after this many microseconds
+pub const MATCHMAKING_TIMEOUT: u64 = 60_000_000;
+
+/// Win rates are in basis points, a player without games on the leaderboard counts as 50%.
+/// Two players are paired when their win rates are within the band, which widens by
+/// `widening` basis points for every second the player waited.
+pub const MAX_WIN_RATE: u32 = 10_000;
+pub const DEFAULT_WIN_RATE: u32 = 5_000;
+pub const DEFAULT_MATCHMAKING_BAND: u32 = 1_000;
+pub const DEFAULT_MATCHMAKING_WIDENING: u32 = 100;
+
+/// Pending game results applied by StartLeaderBoard, the rest are applied with DrainPendingResults
+pub const PENDING_RESULTS_BATCH: u32 = 50;
+
+/// A player can split up to this many hands in a single game against the dealer
+pub const MAX_SPLIT_HANDS: usize = 4;
@@ -1,7 +1,5 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
-mod count;
-mod constants;
 mod random;
 mod state;
 
@@ -10,11 +8,11 @@ use linera_sdk::{
     views::{RootView, View},
     Contract, ContractRuntime,
 };
-use black_jack_chain::{BlackJackParameters, BlackJackMessage, CardOperation, Status, PlayData, LastAction, History, Player, GameState, Insight, VersionAnalytics, PlayerStatus};
+use linera_sdk::base::{Account, Amount, ChainId, CryptoHash, Owner, Timestamp};
+use black_jack_chain::{BlackJackParameters, BlackJackInstantiation, BlackJackMessage, BlackJackError, CardOperation, CardResponse, Status, PlayData, PlayHand, Card, Hand, LastAction, History, Leaderboard, Player, GameEndReason, GameEvent, GameLogEntry, MatchAssignment, MatchDecision, MatchKey, OutboxEntry, QueuedPlayer, RankKey, Season, SeatOutcome, SeatResult, GameMode, GameState, Insight, VersionAnalytics, PlayerStatus, PlayerProfile, HouseEntropy, commit_secret, combine_reveals, game_id, rate_game};
+use black_jack_chain::constants::*;
 use self::state::BlackJack;
-use crate::count::*;
 use crate::random::*;
-use crate::constants::*;
 
 pub struct BlackJackContract {
     state: BlackJack,
@@ -30,7 +28,7 @@ impl WithContractAbi for BlackJackContract {
 impl Contract for BlackJackContract {
     type Message = BlackJackMessage;
     type Parameters = BlackJackParameters;
-    type InstantiationArgument = ();
+    type InstantiationArgument = BlackJackInstantiation;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = BlackJack::load(runtime.root_view_storage_context())
@@ -39,14 +37,14 @@ impl Contract for BlackJackContract {
         BlackJackContract { state, runtime }
     }
 
-    async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
+    async fn instantiate(&mut self, argument: Self::InstantiationArgument) {
         log::info!("App Initialization");
 
         // validate that the application parameters were configured correctly.
         let app_params = self.runtime.application_parameters();
         log::info!("Leaderboard Chain ID: {}", app_params.leaderboard_chain_id);
 
-        if let Some(_owner) = self.runtime.authenticated_signer() {
+        if let Some(owner) = self.runtime.authenticated_signer() {
             let chain_id = self.runtime.chain_id();
 
             // make sure runtime Chain ID is :
@@ -62,171 +60,41 @@ impl Contract for BlackJackContract {
             assert_ne!(app_params.analytics_chain_id, app_params.player_status_chain_id, "Analytics ChainID must be different than Player Status ChainID");
             assert_ne!(app_params.room_status_chain_id, app_params.player_status_chain_id, "Room Status ChainID must be different than Player Status ChainID");
 
-            // set leaderboard to accept stats
-            self.state.leaderboard_on.set(true);
-        }
-    }
-
-    async fn execute_operation(&mut self, _operation: Self::Operation) -> Self::Response {
-        match _operation {
-            CardOperation::Join { player_id, player_name, version, gid } => {
-                log::info!("CardOperation::Join");
-
-                // root chain are not allowed to play
-                self.check_root_invocation();
-
-                let game_state = self.state.game_state.get_mut();
-                let current_time = self.runtime.system_time();
-
-                match game_state.status {
-                    Status::Idle => {
-                        let player_one = self.state.p1.get_mut();
-                        player_one.id = player_id;
-                        player_one.name = player_name.clone();
-                        player_one.gid = gid.clone();
-                        game_state.status = Status::Waiting;
-                        game_state.last_update = current_time;
-                    }
-                    Status::Waiting => {
-                        let time_elapsed = current_time.micros() - game_state.last_update.micros();
-
-                        // reset if last game status update is more than 18 seconds
-                        if time_elapsed >= UNIX_MICRO_IN_18_SECONDS {
-                            // change status to Waiting for Player 2
-                            game_state.status = Status::Waiting;
-                            game_state.last_update = current_time;
-
-                            // let new people join because previous game is inactive for more than 18 seconds
-                            self.reset_and_register_new_player(player_id, player_name.clone(), gid.clone());
-
-                            // send message for room status update, analytics, and player status
-                            self.send_room_status_update().await;
-                            self.send_app_version_analytics(version).await;
-                            self.send_player_join_update(player_name, gid).await;
-
-                            return;
-                        }
-
-                        let player_one = self.state.p1.get();
-                        if player_one.name.to_lowercase() == player_name.to_lowercase() || player_one.id == player_id {
-                            panic!("unable to start, both players have similar name or ID");
-                        }
-
-                        let player_two = self.state.p2.get_mut();
-                        player_two.id = player_id.clone();
-                        player_two.name = player_name.clone();
-                        player_two.gid = gid.clone();
-                        game_state.status = Status::Started;
-                        game_state.last_update = current_time;
-                        self.start_game(player_id, player_name.clone()).await;
-                    }
-                    Status::Started => {
-                        let time_elapsed = current_time.micros() - game_state.last_update.micros();
-
-                        // panic if last game status update is less than 18 seconds
-                        if time_elapsed <= UNIX_MICRO_IN_18_SECONDS {
-                            panic!("blackjack have started");
-                        }
-
-                        // change status to Waiting for Player 2
-                        game_state.status = Status::Waiting;
-                        game_state.last_update = current_time;
-
-                        // let new people join because previous game is inactive for more than 18 seconds
-                        self.reset_and_register_new_player(player_id, player_name.clone(), gid.clone());
-                    }
-                    Status::Finish => {
-                        // change status to Waiting for Player 2
-                        game_state.status = Status::Waiting;
-                        game_state.last_update = current_time;
-
-                        // start new game
-                        self.reset_and_register_new_player(player_id, player_name.clone(), gid.clone());
-                    }
-                }
-
-                // send message for room status update, analytics, and player status
-                self.send_room_status_update().await;
-                self.send_app_version_analytics(version).await;
-                self.send_player_join_update(player_name, gid).await;
-            }
-            CardOperation::Action { player_id, action } => {
-                log::info!("CardOperation::Action");
-
-                // root chain are not allowed to play
-                self.check_root_invocation();
-
-                self.check_game_state();
-                self.check_player(player_id.clone(), false).await;
-
-                match action {
-                    0 => {
-                        // Stand
-                        self.stand(player_id, false).await;
-                    }
-                    1 => {
-                        // Hit
-                        self.hit(player_id).await;
-                    }
-                    _ => {
-                        panic!("action not recognized");
-                    }
-                }
-            }
-            CardOperation::IdleActionCheck { player_id } => {
-                log::info!("CardOperation::IdleActionCheck");
-
-                // root chain are not allowed to play
-                self.check_root_invocation();
-
-                self.check_game_state();
-                self.check_player(player_id.clone(), true).await;
-
-                // panic if last game status update is less than 10 seconds
-                let time_elapsed = self.runtime.system_time().micros() - self.state.game_state.get().last_update.micros();
-                if time_elapsed < UNIX_MICRO_IN_10_SECONDS {
-                    panic!("too early for idle action check");
-                }
-
-                // Stand
-                self.stand(player_id, true).await;
-            }
-            CardOperation::StartLeaderBoard { p } => {
-                log::info!("CardOperation::StartLeaderBoard");
+            // make sure the shoe has 1 to 8 decks and a penetration between 1 and 100 percent
+            assert!(argument.shoe.is_valid(), "invalid shoe settings {:?}", argument.shoe);
 
-                // check Leaderboard authorization
-                self.check_p(p);
-
-                self.state.leaderboard_on.set(true);
-            }
-            CardOperation::StopLeaderBoard { p } => {
-                log::info!("CardOperation::StopLeaderBoard");
+            // make sure both timeouts are within range and the lobby timeout is longer than the turn timeout
+            assert!(argument.timeouts.is_valid(), "invalid timeout settings {:?}", argument.timeouts);
 
-                // check Leaderboard authorization
-                self.check_p(p);
+            // make sure the matchmaking band fits in a win rate
+            assert!(argument.matchmaking.is_valid(), "invalid matchmaking settings {:?}", argument.matchmaking);
 
-                self.state.leaderboard_on.set(false);
-            }
-            CardOperation::ResetLeaderBoard { p } => {
-                log::info!("CardOperation::ResetLeaderBoard");
+            // set leaderboard to accept stats
+            self.state.leaderboard_on.set(true);
 
-                // check Leaderboard authorization
-                self.check_p(p);
+            // room chains request the shoe and timeout settings from Leaderboard chain
+            self.state.shoe_settings.set(argument.shoe);
+            self.state.timeout_settings.set(argument.timeouts);
+            self.state.matchmaking_settings.set(argument.matchmaking);
 
-                self.state.leaderboard.clear();
-                self.state.history.clear();
+            // app creator is the admin when no admin is configured
+            let mut admins = argument.admins;
+            if admins.is_empty() {
+                admins.push(owner);
             }
-            CardOperation::ResetAnalytics { p } => {
-                log::info!("CardOperation::ResetAnalytics");
-
-                // check Analytics authorization
-                self.check_p(p);
-
-                self.state.analytics.clear();
+            for admin in admins {
+                self.state.admins.insert(&admin).unwrap_or_else(|_| { panic!("Failed to add admin {:?}", admin); });
             }
         }
     }
 
+    async fn execute_operation(&mut self, _operation: Self::Operation) -> Self::Response {
+        self.execute_card_operation(_operation).await.unwrap_or_else(|error| {
+            log::info!("CardOperation rejected: {}", error);
+            CardResponse::Error(error)
+        })
+    }
+
     async fn execute_message(&mut self, _message: Self::Message) {
         let is_bouncing = self
             .runtime
@@ -236,61 +104,58 @@ impl Contract for BlackJackContract {
             });
 
         match _message {
-            BlackJackMessage::GameResult { p1, p1gid, p2, p2gid, winner, winner_gid, time } => {
+            BlackJackMessage::GameResult { game_id, players, winner, time, payout, reason } => {
                 log::info!("BlackJackMessage::GameResult");
-                // BlackJackMessage::GameResult not being tracked
-                // Even if it does, bouncing message should do nothing.
+                // rejected by Leaderboard chain, keep it in the outbox of the room chain
                 if is_bouncing {
+                    self.keep_bounced_message(BlackJackMessage::GameResult { game_id, players, winner, time, payout, reason });
                     return;
                 }
 
-                // prevent add stats to leaderboard if status is off
-                if !self.state.leaderboard_on.get() {
-                    panic!("Leaderboard is closed at the moment");
+                // a resent result is only counted once
+                if self.is_known_game(&game_id).await {
+                    return;
                 }
 
-                // load leaderboard
-                let current_leaderboard = self.state.leaderboard.get_mut();
+                let result = History { game_id, players, winner, time, payout, reason };
 
-                // update leaderboard
-                current_leaderboard.update_player(&p1, &winner);
-                current_leaderboard.update_player(&p2, &winner);
-                // current_leaderboard.sort_rank();
-                current_leaderboard.update_count();
-
-                // load gid leaderboard
-                let current_gid_leaderboard = self.state.gid_leaderboard.get_mut();
-
-                // update gid leaderboard
-                current_gid_leaderboard.update_player(&p1gid, &winner_gid);
-                current_gid_leaderboard.update_player(&p2gid, &winner_gid);
-
-                // add game history
-                self.state.history.push_back(History { p1: p1.clone(), p2: p2.clone(), winner, time });
+                // keep the result until the leaderboard is started again, behind the results still pending
+                if !self.state.leaderboard_on.get() || self.state.pending_results.count() > 0 {
+                    self.state.pending_game_ids.insert(&result.game_id).unwrap_or_else(|_| { panic!("Failed to save pending game {:?}", result.game_id); });
+                    self.state.pending_results.push_back(result);
+                    return;
+                }
 
-                // update player status
-                self.send_player_finish_update(p1, p2).await;
+                self.apply_game_result(result).await;
             }
             BlackJackMessage::RoomUpdate { id, status } => {
                 log::info!("BlackJackMessage::RoomUpdate");
-                // BlackJackMessage::RoomUpdate not being tracked
-                // Even if it does, bouncing message should do nothing.
+                // rejected by room status chain, keep it in the outbox of the room chain
                 if is_bouncing {
+                    self.keep_bounced_message(BlackJackMessage::RoomUpdate { id, status });
                     return;
                 }
 
                 // get game status
                 let game_status = status.game_state.status;
 
-                // remove status
+                // matched players who took their seat no longer hold a reservation
+                self.release_reservation(id, &status.seats).await;
+
+                // remove status, the room is free for matchmaking
                 if game_status.eq(&Status::Idle) || game_status.eq(&Status::Finish) {
                     self.state.room_status.remove(&id).unwrap_or_else(|_| { panic!("Room status does not exist for {:?}", id); });
+                    self.state.free_rooms.insert(&id).unwrap_or_else(|_| { panic!("Failed to free room {:?}", id); });
+                    self.state.room_win_rate.remove(&id).unwrap_or_else(|_| { panic!("Failed to reset room win rate {:?}", id); });
+                    self.match_queued_players().await;
                     return;
                 }
 
                 // save or update status
                 if game_status.eq(&Status::Waiting) || game_status.eq(&Status::Started) {
-                    self.state.room_status.insert(&id, status).unwrap_or_else(|_| { panic!("Failed to update room status for {:?}", id); });
+                    self.state.free_rooms.remove(&id).unwrap_or_else(|_| { panic!("Failed to update free rooms for {:?}", id); });
+                    self.state.room_status.insert(&id, *status).unwrap_or_else(|_| { panic!("Failed to update room status for {:?}", id); });
+                    self.match_queued_players().await;
                 }
             }
             BlackJackMessage::Analytic { version } => {
@@ -327,485 +192,2025 @@ impl Contract for BlackJackContract {
                 let player_status = PlayerStatus { gid, time: self.runtime.system_time() };
                 self.state.player_status.insert(&name, player_status).unwrap_or_else(|_| { panic!("Failed to insert {:?}", name); });
             }
-            BlackJackMessage::PlayerFinish { p1, p2 } => {
-                log::info!("BlackJackMessage::PlayerFinish");
-                // BlackJackMessage::PlayerFinish not being tracked
+            BlackJackMessage::MatchLookup { name, gid, chain_id, stake } => {
+                log::info!("BlackJackMessage::MatchLookup");
+                // BlackJackMessage::MatchLookup not being tracked
+                // Even if it does, bouncing message should do nothing.
+                if is_bouncing {
+                    return;
+                }
+
+                // attach the leaderboard win rate and forward to room status chain
+                let win_rate = self.state.leaderboard.get(&name).await
+                    .unwrap_or_else(|_| { panic!("unable to get leaderboard entry"); })
+                    .map_or(DEFAULT_WIN_RATE, |player| player.win_rate());
+                let settings = *self.state.matchmaking_settings.get();
+                let message = BlackJackMessage::MatchRequest { name, gid, chain_id, stake, win_rate, settings };
+                self.runtime
+                    .prepare_message(message)
+                    .send_to(self.runtime.application_parameters().room_status_chain_id);
+            }
+            BlackJackMessage::MatchRequest { name, gid, chain_id, stake, win_rate, settings } => {
+                log::info!("BlackJackMessage::MatchRequest");
+                // BlackJackMessage::MatchRequest not being tracked
                 // Even if it does, bouncing message should do nothing.
                 if is_bouncing {
                     return;
                 }
 
-                // remove Player 1
-                if self.state.player_status.contains_key(&p1).await.unwrap_or(false) {
-                    self.state.player_status.remove(&p1).unwrap_or_else(|_| { panic!("Failed to remove {:?}", p1); });
+                // only Leaderboard chain knows the win rates
+                let origin = self.runtime.message_id()
+                    .unwrap_or_else(|| { panic!("Message ID has to be available when executing a message"); })
+                    .chain_id;
+                if origin != self.runtime.application_parameters().leaderboard_chain_id {
+                    panic!("{}", BlackJackError::Unauthorized);
                 }
+                self.state.matchmaking_settings.set(settings);
 
-                // remove Player 2
-                if self.state.player_status.contains_key(&p2).await.unwrap_or(false) {
-                    self.state.player_status.remove(&p2).unwrap_or_else(|_| { panic!("Failed to remove {:?}", p2); });
+                // player already waiting for a match, names are only unique on the chain that asked
+                let current_time = self.runtime.system_time();
+                let queued = QueuedPlayer { name: name.clone(), gid, chain_id, time: current_time, win_rate, stake };
+                if self.state.match_queue.contains_key(&queued.key()).await.unwrap_or(false) {
+                    return;
                 }
+                self.state.match_queue.insert(&queued.key(), queued).unwrap_or_else(|_| { panic!("Failed to queue {:?}", name); });
+                self.match_queued_players().await;
             }
-        }
-    }
+            BlackJackMessage::MatchFound { name, room, stake } => {
+                log::info!("BlackJackMessage::MatchFound");
+                // BlackJackMessage::MatchFound not being tracked
+                // Even if it does, bouncing message should do nothing.
+                if is_bouncing {
+                    return;
+                }
 
-    async fn store(mut self) {
-        self.state.save().await.expect("Failed to save state");
-    }
-}
+                // client reads the room to join from the service
+                let assignment = MatchAssignment { room, time: self.runtime.system_time() };
+                self.state.matches.insert(&MatchKey { chain_id: self.runtime.chain_id(), name: name.clone(), stake }, assignment).unwrap_or_else(|_| { panic!("Failed to save match for {:?}", name); });
+            }
+            BlackJackMessage::RequestHouseSecret { game_id } => {
+                log::info!("BlackJackMessage::RequestHouseSecret");
+                // BlackJackMessage::RequestHouseSecret not being tracked
+                // Even if it does, bouncing message should do nothing.
+                if is_bouncing {
+                    return;
+                }
 
-impl BlackJackContract {
-    fn check_root_invocation(&mut self) {
-        assert_ne!(self.runtime.chain_id(), self.runtime.application_parameters().leaderboard_chain_id, "Leaderboard chain are not allowed to play");
-        assert_ne!(self.runtime.chain_id(), self.runtime.application_parameters().room_status_chain_id, "Room status chain are not allowed to play");
-        assert_ne!(self.runtime.chain_id(), self.runtime.application_parameters().analytics_chain_id, "Analytics chain are not allowed to play");
-        assert_ne!(self.runtime.chain_id(), self.runtime.application_parameters().player_status_chain_id, "Player Status chain are not allowed to play")
-    }
+                // only the Room Status chain draws house secrets, players don't propose its blocks
+                if self.runtime.chain_id() != self.runtime.application_parameters().room_status_chain_id {
+                    panic!("{}", BlackJackError::Unauthorized);
+                }
+                let origin = self.runtime.message_id()
+                    .unwrap_or_else(|| { panic!("Message ID has to be available when executing a message"); })
+                    .chain_id;
+
+                let entropy = CryptoHash::new(&HouseEntropy {
+                    previous: *self.state.house_entropy.get(),
+                    room: origin,
+                    game_id: game_id.clone(),
+                    block_height: self.runtime.block_height().0,
+                    time: self.runtime.system_time().micros(),
+                });
+                self.state.house_entropy.set(Some(entropy));
+
+                let secret = entropy.to_string();
+                self.runtime
+                    .prepare_message(BlackJackMessage::HouseSecret { game_id, secret })
+                    .send_to(origin);
+            }
+            BlackJackMessage::HouseSecret { game_id, secret } => {
+                log::info!("BlackJackMessage::HouseSecret");
+                // BlackJackMessage::HouseSecret not being tracked
+                // Even if it does, bouncing message should do nothing.
+                if is_bouncing {
+                    return;
+                }
 
-    fn check_p(&mut self, p: String) {
-        assert_eq!(p, self.runtime.application_parameters().leaderboard_pass, "You are not authorized to execute Leaderboard and/or Analytics operation")
-    }
+                // only Room Status chain can reveal for the house
+                let origin = self.runtime.message_id()
+                    .unwrap_or_else(|| { panic!("Message ID has to be available when executing a message"); })
+                    .chain_id;
+                if origin != self.runtime.application_parameters().room_status_chain_id {
+                    panic!("{}", BlackJackError::Unauthorized);
+                }
 
-    fn check_game_state(&mut self) {
-        let state = self.state.game_state.get();
-        if state.status != Status::Started {
-            panic!("game not started yet");
-        }
-    }
+                // secret of a game that is already over or dealt is ignored
+                let house_id = DEALER_ID.to_string();
+                let is_waiting = *self.state.game_mode.get() == GameMode::Dealer
+                    && self.state.game_state.get().status == Status::Started
+                    && *self.state.game_id.get() == game_id
+                    && self.state.deck_seed.get().is_none()
+                    && !self.state.reveals.contains_key(&house_id).await.unwrap_or(false);
+                if !is_waiting {
+                    return;
+                }
 
-    async fn check_player(&mut self, player_id: String, idle_action_check: bool) {
-        if self.state.play_data.contains_key(&player_id).await.unwrap_or(false) {
-            let p = self.state.play_data.get(&player_id).await
-                .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
-            let is_invoker_the_current_player_turn = p.player_id_turn == player_id;
-            if idle_action_check && !is_invoker_the_current_player_turn {
-                // only opponent of current player that can invoke idle action check
-                return;
-            }
-            if idle_action_check && is_invoker_the_current_player_turn {
-                panic!("current player can't do idle action check");
+                self.state.commitments.insert(&house_id, commit_secret(&secret)).unwrap_or_else(|_| {
+                    panic!("Failed to save commitment for {:?}", house_id);
+                });
+                self.state.reveals.insert(&house_id, secret).unwrap_or_else(|_| {
+                    panic!("Failed to save reveal for {:?}", house_id);
+                });
+                self.update_reveal_phase().await;
             }
-            if !is_invoker_the_current_player_turn {
-                panic!("not your turn");
-            }
-        } else {
-            panic!("player not exist");
-        }
-    }
+            BlackJackMessage::RequestTableSettings => {
+                log::info!("BlackJackMessage::RequestTableSettings");
+                // BlackJackMessage::RequestTableSettings not being tracked
+                // Even if it does, bouncing message should do nothing.
+                if is_bouncing {
+                    return;
+                }
 
-    fn reset_and_register_new_player(&mut self, player_id: String, player_name: String, gid: String) {
-        // create Player 1
-        let mut player_one = Player::default();
-        player_one.id = player_id;
-        player_one.name = player_name;
-        player_one.gid = gid;
+                // reply to the requesting room chain
+                let origin = self.runtime.message_id()
+                    .unwrap_or_else(|| { panic!("Message ID has to be available when executing a message"); })
+                    .chain_id;
+                let shoe = *self.state.shoe_settings.get();
+                let timeouts = *self.state.timeout_settings.get();
+                self.runtime
+                    .prepare_message(BlackJackMessage::TableSettings { shoe, timeouts })
+                    .send_to(origin);
+            }
+            BlackJackMessage::TableSettings { shoe, timeouts } => {
+                log::info!("BlackJackMessage::TableSettings");
+                // BlackJackMessage::TableSettings not being tracked
+                // Even if it does, bouncing message should do nothing.
+                if is_bouncing {
+                    return;
+                }
 
-        // update Player 1 data and reset previous game stats
-        self.state.p1.set(player_one);
-        self.state.p2.set(Player::default());
-        self.state.decks.set(Vec::new());
-        self.state.play_data.clear();
-    }
+                // only Leaderboard chain can configure the tables
+                let origin = self.runtime.message_id()
+                    .unwrap_or_else(|| { panic!("Message ID has to be available when executing a message"); })
+                    .chain_id;
+                if origin != self.runtime.application_parameters().leaderboard_chain_id {
+                    panic!("{}", BlackJackError::Unauthorized);
+                }
 
-    async fn send_game_finish_message(&mut self, p1: String, p1gid: String, p2: String, p2gid: String, winner: String, winner_gid: String) {
-        // send message to leaderboard chain
-        let message = BlackJackMessage::GameResult {
-            p1,
-            p1gid,
-            p2,
-            p2gid,
-            winner,
-            winner_gid,
-            time: self.runtime.system_time(),
-        };
-        self.runtime
-            .prepare_message(message)
-            .send_to(self.runtime.application_parameters().leaderboard_chain_id);
-    }
+                // a game in progress keeps its shoe and timeouts, the settings apply to the next table
+                self.state.pending_shoe_settings.set(Some(shoe));
+                self.state.pending_timeout_settings.set(Some(timeouts));
+            }
+            BlackJackMessage::ResetAnalytics => {
+                log::info!("BlackJackMessage::ResetAnalytics");
+                // BlackJackMessage::ResetAnalytics not being tracked
+                // Even if it does, bouncing message should do nothing.
+                if is_bouncing {
+                    return;
+                }
 
-    async fn send_room_status_update(&mut self) {
-        let new_status = Insight {
-            id: self.runtime.chain_id(),
-            game_state: self.state.game_state.get().clone(),
-            p_one: self.state.p1.get().clone(),
-            p_two: self.state.p2.get().clone(),
-        };
+                // only Leaderboard chain admins can reset analytics
+                let origin = self.runtime.message_id()
+                    .unwrap_or_else(|| { panic!("Message ID has to be available when executing a message"); })
+                    .chain_id;
+                if origin != self.runtime.application_parameters().leaderboard_chain_id {
+                    panic!("{}", BlackJackError::Unauthorized);
+                }
 
-        // send message to room status chain
-        let message = BlackJackMessage::RoomUpdate { id: self.runtime.chain_id(), status: new_status };
-        self.runtime
-            .prepare_message(message)
-            .send_to(self.runtime.application_parameters().room_status_chain_id);
-    }
+                self.state.analytics.clear();
+            }
+            BlackJackMessage::PlayerFinish { players } => {
+                log::info!("BlackJackMessage::PlayerFinish");
+                // BlackJackMessage::PlayerFinish not being tracked
+                // Even if it does, bouncing message should do nothing.
+                if is_bouncing {
+                    return;
+                }
 
-    async fn send_app_version_analytics(&mut self, version: String) {
-        // send message to analytics chain
-        let message = BlackJackMessage::Analytic { version };
-        self.runtime
-            .prepare_message(message)
-            .send_to(self.runtime.application_parameters().analytics_chain_id);
+                // remove every player of the finished game
+                for player in players {
+                    if self.state.player_status.contains_key(&player).await.unwrap_or(false) {
+                        self.state.player_status.remove(&player).unwrap_or_else(|_| { panic!("Failed to remove {:?}", player); });
+                    }
+                }
+            }
+        }
     }
 
-    async fn send_player_join_update(&mut self, name: String, gid: String) {
-        // send message to analytics chain
-        let message = BlackJackMessage::PlayerJoin { name, gid };
-        self.runtime
-            .prepare_message(message)
-            .send_to(self.runtime.application_parameters().player_status_chain_id);
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
     }
+}
 
-    async fn send_player_finish_update(&mut self, p1: String, p2: String) {
-        // send message to analytics chain
-        let message = BlackJackMessage::PlayerFinish { p1, p2 };
-        self.runtime
-            .prepare_message(message)
-            .send_to(self.runtime.application_parameters().player_status_chain_id);
-    }
+impl BlackJackContract {
+    async fn execute_card_operation(&mut self, operation: CardOperation) -> Result<CardResponse, BlackJackError> {
+        match operation {
+            CardOperation::Join { player_id, player_name, version, gid, commitment, stake, seats } => {
+                log::info!("CardOperation::Join");
 
-    async fn stand(&mut self, player_id: String, idle_action_check: bool) {
-        let player_one = self.state.p1.get();
-        let player_two = self.state.p2.get();
+                // root chain are not allowed to play
+                self.check_root_invocation()?;
+                self.check_player_name(&player_name)?;
+                self.check_player_id(&player_id, &gid)?;
+                let owner = self.runtime.authenticated_signer().ok_or(BlackJackError::Unauthenticated)?;
+
+                // stake is moved into escrow once the player is seated
+                let stake = stake.unwrap_or(Amount::ZERO);
+                if self.runtime.owner_balance(owner) < stake {
+                    return Err(BlackJackError::InsufficientBalance);
+                }
 
-        // decide next turn id
-        let mut next_turn = player_id.clone();
-        if player_one.id == player_id {
-            next_turn = player_two.id.clone();
-        } else {
-            next_turn = player_one.id.clone();
-        }
+                // seat count is only used by the player who opens the table
+                let seat_count = seats.unwrap_or(DEFAULT_TABLE_SEATS);
+                if !(MIN_TABLE_SEATS..=MAX_TABLE_SEATS).contains(&seat_count) {
+                    return Err(BlackJackError::InvalidSeatCount);
+                }
 
-        // set next turn to invoker player id on idle action check
-        if idle_action_check {
-            next_turn = player_id.clone();
-        }
+                let game_state = self.state.game_state.get().clone();
+                let current_time = self.runtime.system_time();
+                let time_elapsed = current_time.micros() - game_state.last_update.micros();
 
-        // load game data
-        let mut p1_data = self.state.play_data.get(&player_one.id).await
-            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
-        let mut p2_data = self.state.play_data.get(&player_two.id).await
-            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+                let lobby_timeout = self.state.timeout_settings.get().lobby;
+                let open_new_table = match game_state.status {
+                    Status::Idle | Status::Finish => true,
+                    // reset if last game status update is older than the lobby timeout
+                    Status::Waiting => time_elapsed >= lobby_timeout,
+                    Status::Started => {
+                        // reject if last game status update is within the lobby timeout
+                        if time_elapsed <= lobby_timeout {
+                            return Err(BlackJackError::GameAlreadyStarted);
+                        }
+                        true
+                    }
+                };
+
+                if open_new_table {
+                    // let new people join because previous game is over or inactive for longer than the lobby timeout
+                    self.reset_table(GameMode::Duel, seat_count).await;
+                } else {
+                    let seated = self.state.seated_players().await;
+                    if seated.iter().any(|player| player.name.to_lowercase() == player_name.to_lowercase() || player.id == player_id || player.owner == Some(owner)) {
+                        return Err(BlackJackError::SimilarPlayer);
+                    }
 
-        let current_time = self.runtime.system_time();
+                    // every player puts the same amount in the pot
+                    if let Some(player_one) = seated.first() {
+                        if player_one.stake != stake {
+                            return Err(BlackJackError::StakeMismatch);
+                        }
+                    }
+                }
 
-        // check last action
-        // if last action is stand, then the game must end because both player action choose to stand
-        // the winner is player with the biggest score
-        if p1_data.last_action == LastAction::Stand || p2_data.last_action == LastAction::Stand {
-            let p1_score = p1_data.my_score;
-            let p2_score = p2_data.my_score;
+                self.take_seat(Player {
+                    id: player_id.clone(),
+                    name: player_name.clone(),
+                    gid: gid.clone(),
+                    owner: Some(owner),
+                    stake,
+                    ..Player::default()
+                }, commitment).await;
+
+                // cards are dealt after every seat is taken and every player revealed their secret
+                let seated_count = self.state.seats.indices().await.map(|seats| seats.len()).unwrap_or_else(|_| { panic!("unable to read seats"); });
+                if seated_count >= *self.state.seat_count.get() as usize {
+                    self.state.game_state.set(GameState { status: Status::Started, last_update: current_time });
+                    self.start_reveal_phase().await;
+                } else {
+                    // change status to Waiting for the next player
+                    self.state.game_state.set(GameState { status: Status::Waiting, last_update: current_time });
+                }
 
-            let mut winner = String::from("");
-            let mut winner_gid = String::from("");
+                // send message for room status update, analytics, and player status
+                self.send_room_status_update().await;
+                self.send_app_version_analytics(version).await;
+                self.send_player_join_update(player_name, gid).await;
 
-            if p1_score == p2_score {
-                // Draw
-            } else if p1_score > p2_score && p1_score <= 21 || p2_score > 21 {
-                // Player 1 win
-                winner = player_one.name.clone();
-                winner_gid = player_one.gid.clone();
-            } else if p2_score > p1_score && p2_score <= 21 || p1_score > 21 {
-                // Player 2 win
-                winner = player_two.name.clone();
-                winner_gid = player_two.gid.clone();
+                Ok(self.play_data_response(&player_id).await)
             }
+            CardOperation::JoinDealer { player_id, player_name, version, gid, commitment } => {
+                log::info!("CardOperation::JoinDealer");
 
-            // update data
-            p1_data.winner = winner.clone();
-            p1_data.game_state = Status::Finish;
-            p1_data.last_update = current_time;
-            p1_data.player_id_turn = "".to_string();
-            p1_data.opponent_score = p2_data.my_score;
-            p1_data.opponent_card = p2_data.my_card.clone();
+                // root chain are not allowed to play
+                self.check_root_invocation()?;
+                self.check_player_name(&player_name)?;
+                self.check_player_id(&player_id, &gid)?;
+                let owner = self.runtime.authenticated_signer().ok_or(BlackJackError::Unauthenticated)?;
 
-            p2_data.winner = winner.clone();
-            p2_data.game_state = Status::Finish;
-            p2_data.last_update = current_time;
-            p2_data.player_id_turn = "".to_string();
-            p2_data.opponent_score = p1_data.my_score;
-            p2_data.opponent_card = p1_data.my_card.clone();
+                let game_state = self.state.game_state.get();
+                let current_time = self.runtime.system_time();
 
-            // save data to state
-            self.state.game_state.set(GameState { status: Status::Finish, last_update: current_time });
-            self.state.play_data.insert(&player_one.id, p1_data).unwrap_or_else(|_| {
-                panic!("Failed to update Play Data for {:?} - {:?}", player_one.name, player_one.id);
-            });
-            self.state.play_data.insert(&player_two.id, p2_data).unwrap_or_else(|_| {
-                panic!("Failed to update Play Data for {:?} - {:?}", player_two.name, player_two.id);
-            });
+                // a table can only be taken over when it's free or inactive for longer than the lobby timeout
+                if game_state.status == Status::Waiting || game_state.status == Status::Started {
+                    let time_elapsed = current_time.micros() - game_state.last_update.micros();
+                    if time_elapsed <= self.state.timeout_settings.get().lobby {
+                        return Err(BlackJackError::TableInUse);
+                    }
+                }
 
-            // send message to leaderboard chain
-            self.send_game_finish_message(
-                player_one.name.clone(),
-                player_one.gid.clone(),
-                player_two.name.clone(),
-                player_two.gid.clone(),
-                winner,
-                winner_gid,
-            ).await;
-
-            // send room status update
-            self.send_room_status_update().await;
-        } else {
-            // update data
-            p1_data.player_id_turn = next_turn.clone();
-            p1_data.last_action = LastAction::Stand;
-            p1_data.last_update = current_time;
-            p2_data.player_id_turn = next_turn;
-            p2_data.last_action = LastAction::Stand;
-            p2_data.last_update = current_time;
+                // register player against the house, games against the house have no stake
+                self.reset_table(GameMode::Dealer, 2).await;
+                self.take_seat(Player {
+                    id: player_id.clone(),
+                    name: player_name.clone(),
+                    gid: gid.clone(),
+                    owner: Some(owner),
+                    ..Player::default()
+                }, commitment).await;
+                self.state.seats.insert(&1, Player {
+                    id: DEALER_ID.to_string(),
+                    name: DEALER_NAME.to_string(),
+                    gid: DEALER_GID.to_string(),
+                    ..Player::default()
+                }).unwrap_or_else(|_| { panic!("Failed to seat the dealer"); });
+                self.state.game_state.set(GameState { status: Status::Started, last_update: current_time });
+
+                // cards are dealt after the player and the house revealed their secret
+                self.start_reveal_phase().await;
+                self.update_reveal_phase().await;
 
-            // save data to state
-            self.state.game_state.set(GameState { status: Status::Started, last_update: current_time });
-            self.state.play_data.insert(&player_one.id, p1_data).unwrap_or_else(|_| {
-                panic!("Failed to update Play Data for {:?} - {:?}", player_one.name, player_one.id);
-            });
-            self.state.play_data.insert(&player_two.id, p2_data).unwrap_or_else(|_| {
-                panic!("Failed to update Play Data for {:?} - {:?}", player_two.name, player_two.id);
-            });
+                // send message for room status update, analytics, and player status
+                self.send_room_status_update().await;
+                self.send_app_version_analytics(version).await;
+                self.send_player_join_update(player_name, gid).await;
+
+                Ok(self.play_data_response(&player_id).await)
+            }
+            CardOperation::Reveal { secret } => {
+                log::info!("CardOperation::Reveal");
+
+                // root chain are not allowed to play
+                self.check_root_invocation()?;
+
+                self.check_game_state()?;
+                let player_id = self.authenticated_player().await?.id;
+                if self.state.deck_seed.get().is_some() {
+                    return Err(BlackJackError::CardsAlreadyDealt);
+                }
+                if secret.is_empty() {
+                    return Err(BlackJackError::EmptySecret);
+                }
+
+                // secret must match the commitment sent on Join
+                let commitment = self.state.commitments.get(&player_id).await
+                    .unwrap_or_else(|_| { panic!("unable to get commitment"); })
+                    .ok_or(BlackJackError::PlayerNotExist)?;
+                if self.state.reveals.contains_key(&player_id).await.unwrap_or(false) {
+                    return Err(BlackJackError::SecretAlreadyRevealed);
+                }
+                if commit_secret(&secret) != commitment {
+                    return Err(BlackJackError::SecretMismatch);
+                }
+
+                self.state.reveals.insert(&player_id, secret).unwrap_or_else(|_| {
+                    panic!("Failed to save reveal for {:?}", player_id);
+                });
+
+                // the house secret is only drawn on the Room Status chain once the player's secret is on chain,
+                // a player who saw it first could walk away from a bad deck
+                if *self.state.game_mode.get() == GameMode::Dealer {
+                    let game_id = self.state.game_id.get().clone();
+                    self.runtime
+                        .prepare_message(BlackJackMessage::RequestHouseSecret { game_id })
+                        .send_to(self.runtime.application_parameters().room_status_chain_id);
+                }
+                self.update_reveal_phase().await;
+
+                Ok(self.play_data_response(&player_id).await)
+            }
+            CardOperation::Action { action } => {
+                log::info!("CardOperation::Action");
+
+                // root chain are not allowed to play
+                self.check_root_invocation()?;
+
+                self.check_game_state()?;
+                if self.state.deck_seed.get().is_none() {
+                    return Err(BlackJackError::WaitingForReveal);
+                }
+                let player_id = self.check_player(false).await?;
+
+                let game_mode = *self.state.game_mode.get();
+
+                match (action, game_mode) {
+                    (0, GameMode::Duel) => {
+                        // Stand
+                        self.stand(false).await;
+                    }
+                    (1, GameMode::Duel) => {
+                        // Hit
+                        self.hit(player_id.clone()).await;
+                    }
+                    (0, GameMode::Dealer) => {
+                        // Stand against the house
+                        self.stand_against_dealer(player_id.clone()).await;
+                    }
+                    (1, GameMode::Dealer) => {
+                        // Hit against the house
+                        self.hit_against_dealer(player_id.clone()).await;
+                    }
+                    (2, GameMode::Dealer) => {
+                        // Double down against the house
+                        self.double_down_against_dealer(player_id.clone()).await?;
+                    }
+                    (3, GameMode::Dealer) => {
+                        // Split against the house
+                        self.split_against_dealer(player_id.clone()).await?;
+                    }
+                    (4, GameMode::Dealer) => {
+                        // Surrender against the house
+                        self.surrender_against_dealer(player_id.clone()).await?;
+                    }
+                    (2..=4, GameMode::Duel) => {
+                        return Err(BlackJackError::ActionNotAllowed);
+                    }
+                    _ => {
+                        return Err(BlackJackError::ActionNotRecognized);
+                    }
+                }
+
+                Ok(self.play_data_response(&player_id).await)
+            }
+            CardOperation::IdleActionCheck => {
+                log::info!("CardOperation::IdleActionCheck");
+
+                // root chain are not allowed to play
+                self.check_root_invocation()?;
+
+                self.check_game_state()?;
+
+                // the house never idles, anyone can check a table against the house
+                let game_mode = *self.state.game_mode.get();
+                let player_id = match game_mode {
+                    GameMode::Duel => self.check_player(true).await?,
+                    GameMode::Dealer => self.state.seated_players().await.remove(0).id,
+                };
+
+                // reject if last game status update is within the turn timeout
+                let time_elapsed = self.runtime.system_time().micros() - self.state.game_state.get().last_update.micros();
+                if time_elapsed < self.state.timeout_settings.get().turn {
+                    return Err(BlackJackError::IdleCheckTooEarly);
+                }
+
+                if game_mode == GameMode::Dealer {
+                    // player who abandons the house loses the game
+                    self.forfeit_dealer_game().await;
+                } else if self.state.deck_seed.get().is_none() {
+                    // opponent who doesn't reveal their secret in time forfeits the game
+                    if !self.state.reveals.contains_key(&player_id).await.unwrap_or(false) {
+                        return Err(BlackJackError::SecretNotRevealed);
+                    }
+                    self.forfeit_reveal().await;
+                } else {
+                    // Stand
+                    self.stand(true).await;
+                }
+
+                Ok(self.play_data_response(&player_id).await)
+            }
+            CardOperation::LeaveTable => {
+                log::info!("CardOperation::LeaveTable");
+
+                // root chain are not allowed to play
+                self.check_root_invocation()?;
+                self.authenticated_player().await?;
+
+                // seated players get their stake back when nobody else joins before the lobby timeout
+                let game_state = self.state.game_state.get().clone();
+                if game_state.status != Status::Waiting {
+                    return Err(BlackJackError::TableNotWaiting);
+                }
+                let current_time = self.runtime.system_time();
+                if current_time.micros() - game_state.last_update.micros() < self.state.timeout_settings.get().lobby {
+                    return Err(BlackJackError::LobbyNotTimedOut);
+                }
+
+                let seat_count = *self.state.seat_count.get();
+                self.reset_table(GameMode::Duel, seat_count).await;
+                self.state.game_state.set(GameState { status: Status::Idle, last_update: current_time });
+
+                // send room status update
+                self.send_room_status_update().await;
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::StartLeaderBoard => {
+                log::info!("CardOperation::StartLeaderBoard");
+
+                // check Leaderboard authorization
+                self.check_admin().await?;
+
+                self.state.leaderboard_on.set(true);
+
+                // results received while stopped are applied in the order they arrived, the rest with DrainPendingResults
+                self.drain_pending_results(PENDING_RESULTS_BATCH).await;
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::DrainPendingResults { max } => {
+                log::info!("CardOperation::DrainPendingResults");
+
+                // check Leaderboard authorization
+                self.check_admin().await?;
+
+                if !self.state.leaderboard_on.get() {
+                    return Err(BlackJackError::LeaderboardClosed);
+                }
+                self.drain_pending_results(max).await;
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::StopLeaderBoard => {
+                log::info!("CardOperation::StopLeaderBoard");
+
+                // check Leaderboard authorization
+                self.check_admin().await?;
+
+                self.state.leaderboard_on.set(false);
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::ResetLeaderBoard => {
+                log::info!("CardOperation::ResetLeaderBoard");
+
+                // check Leaderboard authorization
+                self.check_admin().await?;
+
+                self.clear_live_leaderboard();
+                self.state.history.clear();
+                self.state.games.clear();
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::DiscardPendingResults { game_ids } => {
+                log::info!("CardOperation::DiscardPendingResults");
+
+                // check Leaderboard authorization
+                self.check_admin().await?;
+
+                // discarded results stay in the queue and are skipped when it's drained
+                for game_id in game_ids {
+                    self.state.pending_game_ids.remove(&game_id).unwrap_or_else(|_| { panic!("Failed to discard pending game {:?}", game_id); });
+                }
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::ResetAnalytics => {
+                log::info!("CardOperation::ResetAnalytics");
+
+                // check Analytics authorization
+                self.check_admin().await?;
+
+                // analytics are kept on the Analytics chain
+                self.runtime
+                    .prepare_message(BlackJackMessage::ResetAnalytics)
+                    .send_to(self.runtime.application_parameters().analytics_chain_id);
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::AddAdmin { owner } => {
+                log::info!("CardOperation::AddAdmin");
+
+                // check Leaderboard authorization
+                self.check_admin().await?;
+
+                self.state.admins.insert(&owner).unwrap_or_else(|_| { panic!("Failed to add admin {:?}", owner); });
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::CreateSeason { name, start, end } => {
+                log::info!("CardOperation::CreateSeason");
+
+                // check Leaderboard authorization
+                self.check_admin().await?;
+
+                // a season that is already over would never be live
+                if start >= end || end <= self.runtime.system_time() {
+                    return Err(BlackJackError::InvalidSeason);
+                }
+                for season in self.all_seasons().await {
+                    if season.overlaps(start, end) {
+                        return Err(BlackJackError::InvalidSeason);
+                    }
+                }
+
+                let id = self.state.season_count.get().saturating_add(1);
+                self.state.season_count.set(id);
+                self.state.seasons.insert(&id, Season { id, name, start, end, archived: false })
+                    .unwrap_or_else(|_| { panic!("Failed to create season {:?}", id); });
+
+                // season may already be running
+                let current_time = self.runtime.system_time();
+                self.route_to_season(current_time).await;
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::FindMatch { player_name, gid, stake } => {
+                log::info!("CardOperation::FindMatch");
+
+                self.check_player_name(&player_name)?;
+                let stake = stake.unwrap_or(Amount::ZERO);
+
+                // previous match is replaced by the next one
+                self.state.matches.remove(&MatchKey { chain_id: self.runtime.chain_id(), name: player_name.clone(), stake }).unwrap_or_else(|_| { panic!("Failed to remove match for {:?}", player_name); });
+
+                // send message to room status chain
+                // Leaderboard chain adds the win rate before the request reaches room status chain
+                let message = BlackJackMessage::MatchLookup { name: player_name, gid, chain_id: self.runtime.chain_id(), stake };
+                self.runtime
+                    .prepare_message(message)
+                    .send_to(self.runtime.application_parameters().leaderboard_chain_id);
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::ResendBouncedMessages => {
+                log::info!("CardOperation::ResendBouncedMessages");
+
+                // root chain are not rooms
+                self.check_root_invocation()?;
+
+                let mut room_update = false;
+                while let Some(entry) = self.state.outbox.front().await.unwrap_or_else(|_| { panic!("unable to read outbox"); }) {
+                    self.state.outbox.delete_front();
+                    match entry.message {
+                        // room status chain only needs the latest status, sent once below
+                        BlackJackMessage::RoomUpdate { .. } => room_update = true,
+                        message => {
+                            self.runtime
+                                .prepare_message(message)
+                                .with_tracking()
+                                .send_to(self.runtime.application_parameters().leaderboard_chain_id);
+                        }
+                    }
+                }
+                if room_update {
+                    self.send_room_status_update().await;
+                }
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::RegisterRoom => {
+                log::info!("CardOperation::RegisterRoom");
+
+                // root chain are not rooms
+                self.check_root_invocation()?;
+
+                // room status chain learns about the room and can match players to it
+                self.send_room_status_update().await;
+
+                Ok(CardResponse::Ok)
+            }
+            CardOperation::RemoveAdmin { owner } => {
+                log::info!("CardOperation::RemoveAdmin");
+
+                // check Leaderboard authorization
+                self.check_admin().await?;
+
+                // keep at least one admin, or nobody could manage the Leaderboard anymore
+                let admins = self.state.admins.indices().await.unwrap_or_else(|_| { panic!("unable to read admins"); });
+                if admins.len() == 1 && admins.contains(&owner) {
+                    return Err(BlackJackError::LastAdmin);
+                }
+
+                self.state.admins.remove(&owner).unwrap_or_else(|_| { panic!("Failed to remove admin {:?}", owner); });
+
+                Ok(CardResponse::Ok)
+            }
+        }
+    }
+
+    fn check_root_invocation(&mut self) -> Result<(), BlackJackError> {
+        let chain_id = self.runtime.chain_id();
+        let app_params = self.runtime.application_parameters();
+        if chain_id == app_params.leaderboard_chain_id {
+            return Err(BlackJackError::LeaderboardChainNotAllowed);
+        }
+        if chain_id == app_params.room_status_chain_id {
+            return Err(BlackJackError::RoomStatusChainNotAllowed);
+        }
+        if chain_id == app_params.analytics_chain_id {
+            return Err(BlackJackError::AnalyticsChainNotAllowed);
+        }
+        if chain_id == app_params.player_status_chain_id {
+            return Err(BlackJackError::PlayerStatusChainNotAllowed);
+        }
+        Ok(())
+    }
+
+    async fn check_admin(&mut self) -> Result<(), BlackJackError> {
+        let signer = self.runtime.authenticated_signer().ok_or(BlackJackError::Unauthenticated)?;
+
+        // admins are kept on the Leaderboard chain
+        if self.runtime.chain_id() != self.runtime.application_parameters().leaderboard_chain_id {
+            return Err(BlackJackError::Unauthorized);
+        }
+        if !self.state.admins.contains(&signer).await.unwrap_or(false) {
+            return Err(BlackJackError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    fn check_player_name(&mut self, player_name: &str) -> Result<(), BlackJackError> {
+        if player_name.eq_ignore_ascii_case(DEALER_NAME) {
+            return Err(BlackJackError::ReservedPlayerName);
+        }
+        Ok(())
+    }
+
+    fn check_player_id(&mut self, player_id: &str, gid: &str) -> Result<(), BlackJackError> {
+        // commitments, reveals and play data of the house are keyed by its id
+        if player_id.eq_ignore_ascii_case(DEALER_ID) || gid.eq_ignore_ascii_case(DEALER_GID) {
+            return Err(BlackJackError::ReservedPlayerId);
+        }
+        Ok(())
+    }
+
+    fn check_game_state(&mut self) -> Result<(), BlackJackError> {
+        let state = self.state.game_state.get();
+        if state.status != Status::Started {
+            return Err(BlackJackError::GameNotStarted);
+        }
+        Ok(())
+    }
+
+    async fn authenticated_player(&mut self) -> Result<Player, BlackJackError> {
+        let signer = self.runtime.authenticated_signer().ok_or(BlackJackError::Unauthenticated)?;
+
+        // player is identified by the block signer, never by the id sent in the operation
+        self.state.seated_players().await
+            .into_iter()
+            .find(|player| player.owner == Some(signer))
+            .ok_or(BlackJackError::PlayerNotExist)
+    }
+
+    async fn check_player(&mut self, idle_action_check: bool) -> Result<String, BlackJackError> {
+        let player_id = self.authenticated_player().await?.id;
+        if self.state.play_data.contains_key(&player_id).await.unwrap_or(false) {
+            let p = self.state.play_data.get(&player_id).await
+                .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+            let is_invoker_the_current_player_turn = p.player_id_turn == player_id;
+            if idle_action_check && !is_invoker_the_current_player_turn {
+                // only opponent of current player that can invoke idle action check
+                return Ok(player_id);
+            }
+            if idle_action_check && is_invoker_the_current_player_turn {
+                return Err(BlackJackError::IdleCheckByCurrentPlayer);
+            }
+            if !is_invoker_the_current_player_turn {
+                return Err(BlackJackError::NotYourTurn);
+            }
+            Ok(player_id)
+        } else {
+            Err(BlackJackError::PlayerNotExist)
+        }
+    }
+
+    async fn play_data_response(&mut self, player_id: &String) -> CardResponse {
+        let mut play_data = self.state.play_data.get(player_id).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); })
+            .unwrap_or_default();
+        play_data.table = self.state.table().await;
+        CardResponse::PlayData(Box::new(play_data))
+    }
+
+    async fn is_known_game(&mut self, game_id: &String) -> bool {
+        self.state.games.contains_key(game_id).await.unwrap_or(false)
+            || self.state.pending_game_ids.contains(game_id).await.unwrap_or(false)
+    }
+
+    async fn drain_pending_results(&mut self, max: u32) {
+        for _ in 0..max {
+            let Some(result) = self.state.pending_results.front().await.unwrap_or_else(|_| { panic!("unable to read pending results"); }) else {
+                break;
+            };
+            self.state.pending_results.delete_front();
+
+            // a result that is no longer pending was discarded
+            if !self.state.pending_game_ids.contains(&result.game_id).await.unwrap_or(false) {
+                continue;
+            }
+            self.state.pending_game_ids.remove(&result.game_id).unwrap_or_else(|_| { panic!("Failed to remove pending game {:?}", result.game_id); });
+            self.apply_game_result(result).await;
+        }
+    }
+
+    async fn update_live_leaderboard(&mut self, players: &[SeatResult], time: Timestamp) {
+        // load leaderboard entries, the house has none and always plays at the default rating
+        let mut entries = Vec::new();
+        for player in players.iter() {
+            let entry = if player.is_house() {
+                None
+            } else {
+                self.state.leaderboard.get(&player.name).await
+                    .unwrap_or_else(|_| { panic!("unable to get leaderboard entry"); })
+            };
+            entries.push(entry);
+        }
+
+        // rate every player from the ratings they had before this game
+        let ratings: Vec<u32> = entries.iter().map(|entry| entry.as_ref().map_or(DEFAULT_RATING, |p| p.rating)).collect();
+        let rating_changes = rate_game(players, &ratings, time);
+
+        // update leaderboard, the house seat is never ranked
+        for ((player, entry), change) in players.iter().zip(entries).zip(rating_changes) {
+            if player.is_house() {
+                continue;
+            }
+            let mut updated = Player::record_game(entry.clone(), &player.name, player.outcome);
+            updated.rating = change.rating;
+            self.update_leaderboard_entry(entry, updated);
+
+            let mut rating_history = self.state.rating_history.get(&player.name).await
+                .unwrap_or_else(|_| { panic!("unable to get rating history"); }).unwrap_or_default();
+            rating_history.push(change);
+            self.state.rating_history.insert(&player.name, rating_history).unwrap_or_else(|_| {
+                panic!("Failed to update rating history for {:?}", player.name);
+            });
+        }
+        let leaderboard_count = self.state.leaderboard_count.get().saturating_add(1);
+        self.state.leaderboard_count.set(leaderboard_count);
+    }
+
+    async fn apply_game_result(&mut self, result: History) {
+        let players = result.players.clone();
+        let time = result.time;
+        let reason = result.reason;
+
+        // live leaderboard holds the standings of the season this game was played in
+        if self.route_to_season(time).await {
+            self.update_live_leaderboard(&players, time).await;
+        }
+
+        // update gid leaderboard
+        for player in players.iter().filter(|player| !player.is_house()) {
+            let entry = self.state.gid_leaderboard.get(&player.gid).await
+                .unwrap_or_else(|_| { panic!("unable to get gid leaderboard entry"); });
+            let updated = Player::record_game(entry.clone(), &player.gid, player.outcome);
+            self.update_gid_leaderboard_entry(entry, updated);
+        }
+
+        // update player profiles
+        for player in players.iter().filter(|player| !player.is_house()) {
+            let profile = self.state.player_profiles.get(&player.gid).await
+                .unwrap_or_else(|_| { panic!("unable to get player profile"); });
+            let profile = PlayerProfile::record_game(profile, player, reason, time);
+            self.state.player_profiles.insert(&player.gid, profile).unwrap_or_else(|_| {
+                panic!("Failed to update player profile for {:?}", player.gid);
+            });
+        }
+
+        // add game history
+        let names = players.iter().map(|player| player.name.clone()).collect();
+        let game_id = result.game_id.clone();
+        self.state.games.insert(&game_id, result.clone()).unwrap_or_else(|_| {
+            panic!("Failed to save game {:?}", game_id);
+        });
+        self.state.history.push_back(result);
+
+        // update player status
+        self.send_player_finish_update(names).await;
+    }
+
+    async fn all_seasons(&mut self) -> Vec<Season> {
+        let mut seasons = Vec::new();
+        for id in self.state.seasons.indices().await.unwrap_or_else(|_| { panic!("unable to read seasons"); }) {
+            let season = self.state.seasons.get(&id).await
+                .unwrap_or_else(|_| { panic!("unable to get season"); }).unwrap_or_else(|| { panic!("unable to get season"); });
+            seasons.push(season);
+        }
+        seasons
+    }
+
+    /// Archive or activate seasons up to now, and tell whether a game played at `time` counts on the live leaderboard
+    async fn route_to_season(&mut self, time: Timestamp) -> bool {
+        let current_time = self.runtime.system_time();
+
+        // archive the live season once it's over
+        if let Some(id) = *self.state.active_season.get() {
+            let season = self.state.seasons.get(&id).await
+                .unwrap_or_else(|_| { panic!("unable to get season"); }).unwrap_or_else(|| { panic!("unable to get season"); });
+            if current_time >= season.end {
+                self.archive_season(season).await;
+            }
+        }
+
+        // a season that is running starts from a blank leaderboard, the standings before it are archived
+        if self.state.active_season.get().is_none() {
+            let next_season = self.all_seasons().await.into_iter().find(|season| !season.archived && season.contains(current_time));
+            if let Some(season) = next_season {
+                if *self.state.leaderboard_count.get() > 0 {
+                    let standings = self.live_standings().await;
+                    self.state.offseason_archive.insert(&season.id, standings).unwrap_or_else(|_| { panic!("Failed to archive standings before season {:?}", season.id); });
+                }
+                self.clear_live_leaderboard();
+                self.state.active_season.set(Some(season.id));
+            }
+        }
+
+        // a game from a room chain lagging behind counts only where it was played
+        match *self.state.active_season.get() {
+            Some(id) => {
+                let season = self.state.seasons.get(&id).await
+                    .unwrap_or_else(|_| { panic!("unable to get season"); }).unwrap_or_else(|| { panic!("unable to get season"); });
+                season.contains(time)
+            }
+            None => !self.all_seasons().await.iter().any(|season| season.contains(time)),
+        }
+    }
+
+    async fn live_standings(&mut self) -> Leaderboard {
+        // standings in ranking order
+        let mut rank = Vec::new();
+        for key in self.state.leaderboard_rank.indices().await.unwrap_or_else(|_| { panic!("unable to read leaderboard rank"); }) {
+            let player = self.state.leaderboard.get(&key.name).await
+                .unwrap_or_else(|_| { panic!("unable to get leaderboard entry"); }).unwrap_or_else(|| { panic!("unable to get leaderboard entry"); });
+            rank.push(player);
+        }
+        Leaderboard { total: rank.len() as u32, count: *self.state.leaderboard_count.get(), rank }
+    }
+
+    async fn archive_season(&mut self, mut season: Season) {
+        // freeze the final standings
+        let standings = self.live_standings().await;
+        self.state.season_archive.insert(&season.id, standings).unwrap_or_else(|_| { panic!("Failed to archive season {:?}", season.id); });
+
+        let id = season.id;
+        season.archived = true;
+        self.state.seasons.insert(&id, season).unwrap_or_else(|_| { panic!("Failed to update season"); });
+        self.state.active_season.set(None);
+
+        // next season starts from a blank leaderboard
+        self.clear_live_leaderboard();
+    }
+
+    fn clear_live_leaderboard(&mut self) {
+        self.state.leaderboard.clear();
+        self.state.leaderboard_rank.clear();
+        self.state.rating_rank.clear();
+        self.state.leaderboard_count.set(0);
+        self.state.leaderboard_players.set(0);
+        self.state.rating_history.clear();
+    }
+
+    fn update_leaderboard_entry(&mut self, previous: Option<Player>, player: Player) {
+        // rank indexes are keyed by the stats, so the previous keys are replaced
+        if let Some(previous) = previous {
+            self.state.leaderboard_rank.remove(&RankKey::by_wins(&previous)).unwrap_or_else(|_| { panic!("Failed to update leaderboard rank"); });
+            self.state.rating_rank.remove(&RankKey::by_rating(&previous)).unwrap_or_else(|_| { panic!("Failed to update rating rank"); });
+        } else {
+            let players = self.state.leaderboard_players.get().saturating_add(1);
+            self.state.leaderboard_players.set(players);
+        }
+        self.state.leaderboard_rank.insert(&RankKey::by_wins(&player)).unwrap_or_else(|_| { panic!("Failed to update leaderboard rank"); });
+        self.state.rating_rank.insert(&RankKey::by_rating(&player)).unwrap_or_else(|_| { panic!("Failed to update rating rank"); });
+        self.state.leaderboard.insert(&player.name.clone(), player).unwrap_or_else(|_| { panic!("Failed to update leaderboard"); });
+    }
+
+    fn update_gid_leaderboard_entry(&mut self, previous: Option<Player>, player: Player) {
+        if let Some(previous) = previous {
+            self.state.gid_leaderboard_rank.remove(&RankKey::by_wins(&previous)).unwrap_or_else(|_| { panic!("Failed to update gid leaderboard rank"); });
+        } else {
+            let players = self.state.gid_leaderboard_players.get().saturating_add(1);
+            self.state.gid_leaderboard_players.set(players);
+        }
+        self.state.gid_leaderboard_rank.insert(&RankKey::by_wins(&player)).unwrap_or_else(|_| { panic!("Failed to update gid leaderboard rank"); });
+        self.state.gid_leaderboard.insert(&player.name.clone(), player).unwrap_or_else(|_| { panic!("Failed to update gid leaderboard"); });
+    }
+
+    async fn reset_table(&mut self, game_mode: GameMode, seat_count: u8) {
+        // a stalled game against the house is settled before the table is taken over
+        if *self.state.game_mode.get() == GameMode::Dealer && self.state.game_state.get().status == Status::Started {
+            self.forfeit_dealer_game().await;
+        }
+
+        // stakes of an abandoned game go back to their owners
+        self.refund_stakes().await;
+
+        // reset previous game stats
+        self.state.seats.clear();
+        self.state.seat_count.set(seat_count);
+        self.state.consecutive_stands.set(0);
+        self.state.game_mode.set(game_mode);
+        self.state.play_data.clear();
+        self.state.commitments.clear();
+        self.state.reveals.clear();
+        self.state.deck_seed.set(None);
+        self.state.game_id.set(String::from(""));
+
+        // settings synced from Leaderboard chain, the next game builds a shoe with them
+        if let Some(shoe) = self.state.pending_shoe_settings.get_mut().take() {
+            self.state.shoe_settings.set(shoe);
+            self.state.decks.set(Vec::new());
+        }
+        if let Some(timeouts) = self.state.pending_timeout_settings.get_mut().take() {
+            self.state.timeout_settings.set(timeouts);
+        }
+
+        // room chain uses the default shoe and timeouts until Leaderboard chain replies
+        if !*self.state.table_settings_requested.get() {
+            self.state.table_settings_requested.set(true);
+            self.runtime
+                .prepare_message(BlackJackMessage::RequestTableSettings)
+                .send_to(self.runtime.application_parameters().leaderboard_chain_id);
+        }
+    }
+
+    async fn take_seat(&mut self, player: Player, commitment: CryptoHash) {
+        let seat = self.state.seats.indices().await.map(|seats| seats.len()).unwrap_or_else(|_| { panic!("unable to read seats"); }) as u8;
+
+        // commitment for the next deck seed
+        self.state.commitments.insert(&player.id, commitment).unwrap_or_else(|_| {
+            panic!("Failed to save commitment for {:?}", player.id);
+        });
+
+        if let Some(owner) = player.owner {
+            self.escrow_stake(owner, player.stake);
+        }
+
+        self.state.seats.insert(&seat, player).unwrap_or_else(|_| {
+            panic!("Failed to take seat {:?}", seat);
+        });
+    }
+
+    fn escrow_stake(&mut self, owner: Owner, stake: Amount) {
+        if stake == Amount::ZERO {
+            return;
+        }
+
+        // move the stake from the player account to the room chain balance
+        let escrow = Account { chain_id: self.runtime.chain_id(), owner: None };
+        self.runtime.transfer(Some(owner), escrow, stake);
+        let held = self.state.escrow.get().saturating_add(stake);
+        self.state.escrow.set(held);
+    }
+
+    fn pay_out(&mut self, owner: Option<Owner>, amount: Amount) {
+        // only stakes still held are paid out, never the balance kept for block fees
+        let held = *self.state.escrow.get();
+        let amount = amount.min(held);
+        self.state.escrow.set(held.saturating_sub(amount));
+
+        // block fees may have used up part of the pot, pay what the room chain still holds
+        let amount = amount.min(self.runtime.chain_balance());
+        if amount == Amount::ZERO {
+            return;
+        }
+        if let Some(owner) = owner {
+            let account = Account { chain_id: self.runtime.chain_id(), owner: Some(owner) };
+            self.runtime.transfer(None, account, amount);
+        }
+    }
+
+    async fn refund_stakes(&mut self) {
+        for (seat, player) in self.state.seated_players().await.into_iter().enumerate() {
+            self.pay_out(player.owner, player.stake);
+            self.state.seats.insert(&(seat as u8), Player { stake: Amount::ZERO, ..player }).unwrap_or_else(|_| {
+                panic!("Failed to update seat {:?}", seat);
+            });
+        }
+    }
+
+    async fn settle_stakes(&mut self, winners: &[usize]) -> Amount {
+        let players = self.state.seated_players().await;
+        let pot = players.iter().fold(Amount::ZERO, |pot, player| pot.saturating_add(player.stake));
+
+        // draw between every seat, each player gets their stake back
+        if winners.is_empty() || winners.len() == players.len() {
+            self.refund_stakes().await;
+            return Amount::ZERO;
+        }
+
+        // winners split the pot, the first winning seat keeps the indivisible remainder
+        let share = u128::from(pot) / winners.len() as u128;
+        let remainder = u128::from(pot) % winners.len() as u128;
+        for (index, seat) in winners.iter().enumerate() {
+            let amount = if index == 0 { share + remainder } else { share };
+            self.pay_out(players[*seat].owner, Amount::from_attos(amount));
+        }
+
+        for (seat, player) in players.into_iter().enumerate() {
+            self.state.seats.insert(&(seat as u8), Player { stake: Amount::ZERO, ..player }).unwrap_or_else(|_| {
+                panic!("Failed to update seat {:?}", seat);
+            });
+        }
+        pot
+    }
+
+    fn winner_name(players: &[Player], winners: &[usize]) -> String {
+        // a single winner takes the game, otherwise the winning seats draw
+        if winners.len() == 1 {
+            players[winners[0]].name.clone()
+        } else {
+            String::from("")
+        }
+    }
+
+    async fn finish_game(&mut self, players: &[Player], winners: &[usize]) {
+        let log = self.state.game_log.get(self.state.game_id.get()).await
+            .unwrap_or_else(|_| { panic!("unable to get game log"); }).unwrap_or_default();
+
+        // seats that let a turn or the reveal time out during the game
+        let timed_out: Vec<u8> = log.iter().flat_map(|entry| match &entry.event {
+            GameEvent::IdleTimeout { seat } => vec![*seat],
+            GameEvent::RevealTimeout { seats } => seats.clone(),
+            _ => vec![],
+        }).collect();
+
+        let mut results = Vec::new();
+        for (seat, player) in players.iter().enumerate() {
+            let play_data = self.state.play_data.get(&player.id).await
+                .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_default();
+
+            // a split hand is settled on its own, the dealt cards only make up the active one
+            let hands = if play_data.my_hands.is_empty() {
+                vec![Hand::new(play_data.my_card)]
+            } else {
+                play_data.my_hands.into_iter().map(|hand| hand.hand).collect::<Vec<Hand>>()
+            };
+
+            // every card drawn after the deal
+            let is_house = player.id == DEALER_ID;
+            let hits: usize = log.iter().map(|entry| match &entry.event {
+                GameEvent::Hit { seat: hit_seat, .. } | GameEvent::DoubleDown { seat: hit_seat, .. } if *hit_seat as usize == seat => 1,
+                GameEvent::Split { seat: split_seat, cards, .. } if *split_seat as usize == seat => cards.len(),
+                GameEvent::DealerDraw { .. } if is_house => 1,
+                _ => 0,
+            }).sum();
+            let outcome = if !winners.contains(&seat) {
+                SeatOutcome::Lose
+            } else if winners.len() == 1 {
+                SeatOutcome::Win
+            } else {
+                SeatOutcome::Draw
+            };
+
+            results.push(SeatResult {
+                seat: seat as u8,
+                name: player.name.clone(),
+                gid: player.gid.clone(),
+                outcome,
+                score: SeatResult::best_score(&hands),
+                hands,
+                hits: hits.min(u8::MAX as usize) as u8,
+                // a timed out seat that still won on score didn't forfeit
+                forfeit: outcome == SeatOutcome::Lose && timed_out.contains(&(seat as u8)),
+            });
+        }
+        let reason = Self::end_reason(&results, winners);
+
+        // pay the pot to the winners, a draw splits it between them
+        let payout = self.settle_stakes(winners).await;
+        self.log_event(GameEvent::Settlement { winners: winners.iter().map(|&seat| seat as u8).collect(), payout }).await;
+
+        // send message to leaderboard chain
+        self.send_game_finish_message(results, Self::winner_name(players, winners), payout, reason).await;
+
+        // send room status update
+        self.send_room_status_update().await;
+    }
+
+    fn end_reason(results: &[SeatResult], winners: &[usize]) -> GameEndReason {
+        // decided by the final hands, a timeout only explains a game the hands don't
+        let forfeited = results.iter().any(|result| result.forfeit);
+        let dealt = results.iter().any(|result| result.hands.iter().any(|hand| !hand.is_empty()));
+
+        if forfeited && !dealt {
+            GameEndReason::IdleForfeit
+        } else if winners.len() != 1 {
+            GameEndReason::Draw
+        } else if results[winners[0]].is_blackjack() {
+            GameEndReason::Blackjack
+        } else if results[winners[0]].score == 21 {
+            GameEndReason::TwentyOne
+        } else if results.iter().enumerate().all(|(seat, result)| seat == winners[0] || result.is_bust()) {
+            GameEndReason::Bust
+        } else if forfeited {
+            GameEndReason::IdleForfeit
+        } else {
+            GameEndReason::StandOff
+        }
+    }
+
+    async fn send_game_finish_message(&mut self, players: Vec<SeatResult>, winner: String, payout: Amount, reason: GameEndReason) {
+        // send message to leaderboard chain
+        let message = BlackJackMessage::GameResult {
+            game_id: self.state.game_id.get().clone(),
+            players,
+            winner,
+            time: self.runtime.system_time(),
+            payout,
+            reason,
+        };
+        self.runtime
+            .prepare_message(message)
+            .with_tracking()
+            .send_to(self.runtime.application_parameters().leaderboard_chain_id);
+    }
+
+    fn keep_bounced_message(&mut self, message: BlackJackMessage) {
+        let entry = OutboxEntry { time: self.runtime.system_time(), message };
+        self.state.outbox.push_back(entry);
+    }
+
+    async fn send_room_status_update(&mut self) {
+        let new_status = Insight {
+            id: self.runtime.chain_id(),
+            game_id: self.state.game_id.get().clone(),
+            game_mode: *self.state.game_mode.get(),
+            game_state: self.state.game_state.get().clone(),
+            seat_count: *self.state.seat_count.get(),
+            seats: self.state.seated_players().await,
+        };
+
+        // send message to room status chain
+        let message = BlackJackMessage::RoomUpdate { id: self.runtime.chain_id(), status: Box::new(new_status) };
+        self.runtime
+            .prepare_message(message)
+            .with_tracking()
+            .send_to(self.runtime.application_parameters().room_status_chain_id);
+    }
+
+    async fn send_app_version_analytics(&mut self, version: String) {
+        // send message to analytics chain
+        let message = BlackJackMessage::Analytic { version };
+        self.runtime
+            .prepare_message(message)
+            .send_to(self.runtime.application_parameters().analytics_chain_id);
+    }
+
+    async fn send_player_join_update(&mut self, name: String, gid: String) {
+        // send message to analytics chain
+        let message = BlackJackMessage::PlayerJoin { name, gid };
+        self.runtime
+            .prepare_message(message)
+            .send_to(self.runtime.application_parameters().player_status_chain_id);
+    }
+
+    async fn send_player_finish_update(&mut self, players: Vec<String>) {
+        // send message to analytics chain
+        let message = BlackJackMessage::PlayerFinish { players };
+        self.runtime
+            .prepare_message(message)
+            .send_to(self.runtime.application_parameters().player_status_chain_id);
+    }
+
+    async fn queued_players(&mut self) -> Vec<QueuedPlayer> {
+        let mut queue = Vec::new();
+        for key in self.state.match_queue.indices().await.unwrap_or_else(|_| { panic!("unable to read match queue"); }) {
+            let queued = self.state.match_queue.get(&key).await
+                .unwrap_or_else(|_| { panic!("unable to get queued player"); }).unwrap_or_else(|| { panic!("unable to get queued player"); });
+            queue.push(queued);
         }
+
+        // arrival order
+        queue.sort_by(|a, b| a.time.cmp(&b.time).then(a.name.cmp(&b.name)));
+        queue
     }
 
-    async fn hit(&mut self, player_id: String) {
-        let player_one = self.state.p1.get();
-        let player_two = self.state.p2.get();
+    async fn match_queued_players(&mut self) {
+        let current_time = self.runtime.system_time();
+        self.evict_expired_matches(current_time).await;
+        let settings = *self.state.matchmaking_settings.get();
+
+        // players who waited the longest are matched first
+        'matching: loop {
+            let queue = self.queued_players().await;
+            for (index, player) in queue.iter().enumerate() {
+                let band = settings.band_after(current_time.micros() - player.time.micros());
+
+                // fill the first waiting room with a free seat and a close win rate
+                if let Some((room, room_win_rate)) = self.waiting_room_with_free_seat(player, band).await {
+                    self.match_players(room, vec![player.clone()], room_win_rate, band, current_time).await;
+                    continue 'matching;
+                }
+
+                // pair with the next player in the band of either of them, the band widens while waiting
+                for partner in queue.iter().skip(index + 1) {
+                    // every player at a table puts the same stake in the pot
+                    if partner.stake != player.stake {
+                        continue;
+                    }
+                    let band = band.max(settings.band_after(current_time.micros() - partner.time.micros()));
+                    if player.win_rate.abs_diff(partner.win_rate) > band {
+                        continue;
+                    }
+                    // no free room, later players may still fill a waiting room
+                    let Some(room) = self.free_room().await else {
+                        break;
+                    };
+                    self.match_players(room, vec![player.clone(), partner.clone()], None, band, current_time).await;
+                    continue 'matching;
+                }
+            }
+            break;
+        }
+    }
+
+    async fn match_players(&mut self, room: ChainId, players: Vec<QueuedPlayer>, room_win_rate: Option<u32>, band: u32, current_time: Timestamp) {
+        let names: Vec<String> = players.iter().map(|player| player.name.clone()).collect();
+        let win_rates: Vec<u32> = players.iter().map(|player| player.win_rate).collect();
+
+        // room keeps the win rate of the players it was opened for
+        if room_win_rate.is_none() {
+            let average = win_rates.iter().sum::<u32>() / win_rates.len().max(1) as u32;
+            self.state.room_win_rate.insert(&room, average).unwrap_or_else(|_| { panic!("Failed to save room win rate"); });
+        }
+
+        // every pairing is logged for fairness audits
+        self.state.match_log.push_back(MatchDecision {
+            time: current_time,
+            room,
+            players: names.clone(),
+            win_rates,
+            room_win_rate,
+            band,
+        });
+
+        self.reserve_room(room, names, current_time).await;
+        for player in players {
+            self.state.match_queue.remove(&player.key()).unwrap_or_else(|_| { panic!("Failed to dequeue {:?}", player.name); });
+            self.send_match_found(player.name, player.chain_id, room, player.stake);
+        }
+    }
+
+    async fn evict_expired_matches(&mut self, current_time: Timestamp) {
+        // players who waited too long leave the queue
+        for queued in self.queued_players().await {
+            if current_time.micros() - queued.time.micros() >= MATCHMAKING_TIMEOUT {
+                self.state.match_queue.remove(&queued.key()).unwrap_or_else(|_| { panic!("Failed to dequeue {:?}", queued.name); });
+            }
+        }
+
+        // matched players who never joined release their seat
+        for room in self.state.reserved_rooms.indices().await.unwrap_or_else(|_| { panic!("unable to read reserved rooms"); }) {
+            let reservation = self.state.reserved_rooms.get(&room).await
+                .unwrap_or_else(|_| { panic!("unable to get reservation"); }).unwrap_or_default();
+            if current_time.micros() - reservation.time.micros() >= MATCHMAKING_TIMEOUT {
+                self.state.reserved_rooms.remove(&room).unwrap_or_else(|_| { panic!("Failed to release room {:?}", room); });
+            }
+        }
+    }
+
+    async fn waiting_room_with_free_seat(&mut self, player: &QueuedPlayer, band: u32) -> Option<(ChainId, Option<u32>)> {
+        for room in self.state.room_status.indices().await.unwrap_or_else(|_| { panic!("unable to read room status"); }) {
+            let insight = self.state.room_status.get(&room).await
+                .unwrap_or_else(|_| { panic!("unable to get insight"); }).unwrap_or_else(|| { panic!("unable to get insight"); });
+            if insight.game_state.status != Status::Waiting || insight.seats.iter().any(|seated| seated.name == player.name) {
+                continue;
+            }
+
+            // player who opened the table set the stake
+            if insight.seats.first().is_some_and(|seated| seated.stake != player.stake) {
+                continue;
+            }
+
+            // rooms opened without matchmaking accept any win rate
+            let room_win_rate = self.state.room_win_rate.get(&room).await.unwrap_or_else(|_| { panic!("unable to get room win rate"); });
+            if room_win_rate.is_some_and(|win_rate| win_rate.abs_diff(player.win_rate) > band) {
+                continue;
+            }
+
+            let reserved = self.state.reserved_rooms.get(&room).await
+                .unwrap_or_else(|_| { panic!("unable to get reservation"); }).unwrap_or_default().players.len();
+            if insight.seats.len() + reserved < insight.seat_count as usize {
+                return Some((room, room_win_rate));
+            }
+        }
+        None
+    }
+
+    async fn free_room(&mut self) -> Option<ChainId> {
+        for room in self.state.free_rooms.indices().await.unwrap_or_else(|_| { panic!("unable to read free rooms"); }) {
+            if !self.state.reserved_rooms.contains_key(&room).await.unwrap_or(false) {
+                return Some(room);
+            }
+        }
+        None
+    }
+
+    async fn reserve_room(&mut self, room: ChainId, players: Vec<String>, current_time: Timestamp) {
+        let mut reservation = self.state.reserved_rooms.get(&room).await
+            .unwrap_or_else(|_| { panic!("unable to get reservation"); }).unwrap_or_default();
+        reservation.players.extend(players);
+        reservation.time = current_time;
+        self.state.reserved_rooms.insert(&room, reservation).unwrap_or_else(|_| { panic!("Failed to reserve room {:?}", room); });
+    }
+
+    async fn release_reservation(&mut self, room: ChainId, seats: &[Player]) {
+        let Some(mut reservation) = self.state.reserved_rooms.get(&room).await.unwrap_or_else(|_| { panic!("unable to get reservation"); }) else {
+            return;
+        };
+        reservation.players.retain(|name| !seats.iter().any(|player| player.name == *name));
+        if reservation.players.is_empty() {
+            self.state.reserved_rooms.remove(&room).unwrap_or_else(|_| { panic!("Failed to release room {:?}", room); });
+        } else {
+            self.state.reserved_rooms.insert(&room, reservation).unwrap_or_else(|_| { panic!("Failed to reserve room {:?}", room); });
+        }
+    }
+
+    fn send_match_found(&mut self, name: String, chain_id: ChainId, room: ChainId, stake: Amount) {
+        // tell the player's chain which room chain to join
+        self.runtime
+            .prepare_message(BlackJackMessage::MatchFound { name, room, stake })
+            .send_to(chain_id);
+    }
+
+    async fn log_event(&mut self, event: GameEvent) {
+        let game_id = self.state.game_id.get().clone();
+        let mut log = self.state.game_log.get(&game_id).await
+            .unwrap_or_else(|_| { panic!("unable to get game log"); }).unwrap_or_default();
+        log.push(GameLogEntry { time: self.runtime.system_time(), event });
+        self.state.game_log.insert(&game_id, log).unwrap_or_else(|_| {
+            panic!("Failed to update game log for {:?}", game_id);
+        });
+    }
 
-        // decide next turn id
-        let mut next_turn = player_id.clone();
-        if player_one.id == player_id {
-            next_turn = player_two.id.clone();
+    async fn stand(&mut self, idle_timeout: bool) {
+        let players = self.state.seated_players().await;
+        let table = self.load_table(&players).await;
+
+        // the player on turn stands, idle action check stands on their behalf
+        let seat = players.iter().position(|player| player.id == table[0].player_id_turn).unwrap_or_else(|| {
+            panic!("unable to find player on turn");
+        });
+        if idle_timeout {
+            self.log_event(GameEvent::IdleTimeout { seat: seat as u8 }).await;
         } else {
-            next_turn = player_one.id.clone();
+            self.log_event(GameEvent::Stand { seat: seat as u8, hand: 0 }).await;
         }
 
-        // load game data
-        let current_decks = self.state.decks.get_mut();
-        let mut p1_data = self.state.play_data.get(&player_one.id).await
-            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
-        let mut p2_data = self.state.play_data.get(&player_two.id).await
-            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+        // players still in the game, busted players can't play anymore
+        let active: Vec<usize> = (0..table.len()).filter(|&index| table[index].my_score <= 21).collect();
+
+        // game ends when every player still in the game choose to stand one after another
+        // the winner is player with the biggest score
+        let stands = self.state.consecutive_stands.get().saturating_add(1);
+        if stands as usize >= active.len() {
+            let best_score = active.iter().map(|&index| table[index].my_score).max().unwrap_or(0);
+            let winners: Vec<usize> = active.into_iter().filter(|&index| table[index].my_score == best_score).collect();
+            self.finish_table(players, table, winners, LastAction::Stand).await;
+        } else {
+            self.state.consecutive_stands.set(stands);
+            let next_turn = players[Self::next_seat(&table, seat)].id.clone();
+            self.continue_table(players, table, next_turn, LastAction::Stand);
+        }
+    }
+
+    async fn hit(&mut self, player_id: String) {
+        let players = self.state.seated_players().await;
+        let mut table = self.load_table(&players).await;
+
+        let seat = players.iter().position(|player| player.id == player_id).unwrap_or_else(|| {
+            panic!("unable to find player {:?}", player_id);
+        });
 
         // initial check to find out if any player have blackjack (21) score
-        let p1_have_blackjack = p1_data.my_score == 21;
-        let p2_have_blackjack = p2_data.my_score == 21;
+        let blackjack: Vec<usize> = (0..table.len()).filter(|&index| table[index].my_score == 21).collect();
 
         // player turn
-        if player_one.id == player_id {
-            // P1
-            let ts = self.runtime.system_time().to_string();
-            let index = random_index(ts, current_decks.len() as u8, player_one.id.clone(), "P1".to_string());
-            let chosen_card = current_decks.get(index as usize).unwrap_or_else(|| {
-                panic!("unable to draw card");
-            });
-            p1_data.my_card.push(*chosen_card);
-            p2_data.opponent_card.push(*chosen_card);
-            p1_data.my_score = calculate_player_score(*chosen_card, &p1_data.my_card, p1_data.my_score);
-            p2_data.opponent_score = calculate_player_score(*chosen_card, &p2_data.opponent_card, p2_data.opponent_score);
-            current_decks.swap_remove(index as usize);
+        let chosen_card = self.draw_card();
+        self.log_event(GameEvent::Hit { seat: seat as u8, hand: 0, card: chosen_card }).await;
+        let data = &mut table[seat];
+        data.my_card.push(chosen_card);
+        data.my_score = Hand::new(data.my_card.clone()).value();
+        data.my_hands = vec![PlayHand::new(data.my_card.clone())];
+        self.state.consecutive_stands.set(0);
+
+        // check turn result for winner
+        let active: Vec<usize> = (0..table.len()).filter(|&index| table[index].my_score <= 21).collect();
+        let winners = if !blackjack.is_empty() {
+            blackjack
+        } else if table[seat].my_score == 21 {
+            vec![seat]
+        } else if active.len() == 1 {
+            // every other player went bust
+            active
+        } else {
+            vec![]
+        };
+
+        if winners.is_empty() {
+            let next_turn = players[Self::next_seat(&table, seat)].id.clone();
+            self.continue_table(players, table, next_turn, LastAction::Hit);
         } else {
-            // P2
-            let ts = self.runtime.system_time().to_string();
-            let index = random_index(ts, current_decks.len() as u8, player_two.id.clone(), "P2".to_string());
-            let chosen_card = current_decks.get(index as usize).unwrap_or_else(|| {
-                panic!("unable to draw card");
+            self.finish_table(players, table, winners, LastAction::Hit).await;
+        }
+    }
+
+    async fn load_table(&mut self, players: &[Player]) -> Vec<PlayData> {
+        let mut table = Vec::new();
+        for player in players.iter() {
+            let data = self.state.play_data.get(&player.id).await
+                .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+            table.push(data);
+        }
+        table
+    }
+
+    fn save_table(&mut self, players: &[Player], mut table: Vec<PlayData>, finished: bool) {
+        // every player sees the hand of the next seat, the hole card stays hidden until the game finish
+        let hands: Vec<Hand> = table.iter().map(|data| Hand::new(data.my_card.clone())).collect();
+        for (seat, data) in table.iter_mut().enumerate() {
+            let next_hand = &hands[(seat + 1) % hands.len()];
+            let visible_hand = if finished { next_hand.clone() } else { next_hand.face_up() };
+            data.opponent_score = visible_hand.value();
+            data.opponent_card = visible_hand.cards;
+        }
+
+        for (player, data) in players.iter().zip(table) {
+            self.state.play_data.insert(&player.id, data).unwrap_or_else(|_| {
+                panic!("Failed to update Play Data for {:?} - {:?}", player.name, player.id);
             });
-            p2_data.my_card.push(*chosen_card);
-            p1_data.opponent_card.push(*chosen_card);
-            p2_data.my_score = calculate_player_score(*chosen_card, &p2_data.my_card, p2_data.my_score);
-            p1_data.opponent_score = calculate_player_score(*chosen_card, &p1_data.opponent_card, p1_data.opponent_score);
-            current_decks.swap_remove(index as usize);
         }
+    }
 
-        // check turn result for winner
-        let p1_score = p1_data.my_score;
-        let p2_score = p2_data.my_score;
+    fn next_seat(table: &[PlayData], seat: usize) -> usize {
+        // turns rotate in seat order and skip busted players
+        (1..=table.len())
+            .map(|step| (seat + step) % table.len())
+            .find(|&index| table[index].my_score <= 21)
+            .unwrap_or(seat)
+    }
 
-        let mut winner_exist = false;
-        let mut winner = String::from("");
-        let mut winner_gid = String::from("");
+    fn continue_table(&mut self, players: Vec<Player>, mut table: Vec<PlayData>, next_turn: String, last_action: LastAction) {
+        let current_time = self.runtime.system_time();
 
-        if p1_have_blackjack && p2_have_blackjack {
-            // Draw
-            winner_exist = true;
-        } else if p1_have_blackjack {
-            // Player 1 win
-            winner_exist = true;
-            winner = player_one.name.clone();
-            winner_gid = player_one.gid.clone();
-        } else if p2_have_blackjack {
-            // Player 2 win
-            winner_exist = true;
-            winner = player_two.name.clone();
-            winner_gid = player_two.gid.clone();
-        } else if p1_score == 21 || p2_score > 21 {
-            // Player 1 win
-            winner_exist = true;
-            winner = player_one.name.clone();
-            winner_gid = player_one.gid.clone();
-        } else if p2_score == 21 || p1_score > 21 {
-            // Player 2 win
-            winner_exist = true;
-            winner = player_two.name.clone();
-            winner_gid = player_two.gid.clone();
+        // update data
+        for data in table.iter_mut() {
+            data.player_id_turn = next_turn.clone();
+            data.last_action = last_action;
+            data.last_update = current_time;
+        }
+
+        // save data to state
+        self.state.game_state.set(GameState { status: Status::Started, last_update: current_time });
+        self.save_table(&players, table, false);
+    }
+
+    async fn finish_table(&mut self, players: Vec<Player>, mut table: Vec<PlayData>, winners: Vec<usize>, last_action: LastAction) {
+        let current_time = self.runtime.system_time();
+        let winner = Self::winner_name(&players, &winners);
+
+        // update data
+        for data in table.iter_mut() {
+            data.winner = winner.clone();
+            data.game_state = Status::Finish;
+            data.last_action = last_action;
+            data.last_update = current_time;
+            data.player_id_turn = "".to_string();
+        }
+
+        // save data to state
+        self.state.game_state.set(GameState { status: Status::Finish, last_update: current_time });
+        self.save_table(&players, table, true);
+
+        self.finish_game(&players, &winners).await;
+    }
+
+    async fn start_game(&mut self) {
+        let deck_seed = self.state.deck_seed.get().unwrap_or_else(|| {
+            panic!("deck seed not revealed yet");
+        });
+
+        let game_number = *self.state.game_count.get();
+
+        // shoe is kept across games and rebuilt once the cut card is reached
+        let shoe_settings = *self.state.shoe_settings.get();
+        if self.state.decks.get().len() <= shoe_settings.cut_card() {
+            self.state.decks.set(shoe_settings.new_shoe());
+        }
+
+        // cards left in the shoe are shuffled again from this game's deck seed
+        shuffle_shoe(self.state.decks.get_mut(), deck_seed, game_number);
+        self.state.dealt_cards.set(Vec::new());
+
+        let players = self.state.seated_players().await;
+
+        // every seat gets a first card face down, then a second card face up
+        let mut hands = vec![Hand::default(); players.len()];
+        for _ in 0..2 {
+            for hand in hands.iter_mut() {
+                hand.push(self.draw_card());
+            }
+        }
+
+        for (seat, hand) in hands.iter().enumerate() {
+            self.log_event(GameEvent::Deal { seat: seat as u8, cards: hand.cards.clone() }).await;
         }
 
         let current_time = self.runtime.system_time();
 
-        if winner_exist {
-            // update data
-            p1_data.winner = winner.clone();
-            p1_data.game_state = Status::Finish;
-            p1_data.last_action = LastAction::Hit;
-            p1_data.last_update = current_time;
-            p1_data.player_id_turn = "".to_string();
-            p1_data.opponent_score = p2_data.my_score;
-            p1_data.opponent_card = p2_data.my_card.clone();
-
-            p2_data.winner = winner.clone();
-            p2_data.game_state = Status::Finish;
-            p2_data.last_action = LastAction::Hit;
-            p2_data.last_update = current_time;
-            p2_data.player_id_turn = "".to_string();
-            p2_data.opponent_score = p1_data.my_score;
-            p2_data.opponent_card = p1_data.my_card.clone();
+        // save play data
+        let game_id = self.state.game_id.get().clone();
+        let table = hands.into_iter().map(|hand| PlayData {
+            game_id: game_id.clone(),
+            game_number,
+            p_one_id: players[0].id.clone(),
+            p_two_id: players[1].id.clone(),
+            my_hands: vec![PlayHand::new(hand.cards.clone())],
+            my_score: hand.value(),
+            my_card: hand.cards,
+            player_id_turn: players[0].id.clone(),
+            last_action: LastAction::None,
+            winner: String::from(""),
+            game_state: Status::Started,
+            last_update: current_time,
+            deck_seed: Some(deck_seed),
+            ..PlayData::default()
+        }).collect();
 
-            // save data to state
-            self.state.game_state.set(GameState { status: Status::Finish, last_update: current_time });
-            self.state.play_data.insert(&player_one.id, p1_data).unwrap_or_else(|_| {
-                panic!("Failed to update Play Data for {:?} - {:?}", player_one.name, player_one.id);
+        self.state.consecutive_stands.set(0);
+        self.save_table(&players, table, false);
+    }
+
+    fn draw_card(&mut self) -> Card {
+        // shoe played until empty, refill it in the middle of the game without the cards in play
+        if self.state.decks.get().is_empty() {
+            let deck_seed = self.state.deck_seed.get().unwrap_or_else(|| {
+                panic!("deck seed not revealed yet");
+            });
+            let game_number = *self.state.game_count.get();
+            let mut in_play = self.state.dealt_cards.get().clone();
+            let shoe = self.state.shoe_settings.get().new_shoe().into_iter().filter(|card| {
+                match in_play.iter().position(|dealt| dealt == card) {
+                    Some(index) => {
+                        in_play.swap_remove(index);
+                        false
+                    }
+                    None => true,
+                }
+            }).collect();
+            self.state.decks.set(shoe);
+            shuffle_shoe(self.state.decks.get_mut(), deck_seed, game_number);
+        }
+
+        // shoe is already shuffled, so the next card is on top
+        let card = self.state.decks.get_mut().pop().unwrap_or_else(|| {
+            panic!("unable to draw card");
+        });
+        self.state.dealt_cards.get_mut().push(card);
+        card.face
+    }
+
+    async fn start_reveal_phase(&mut self) {
+        let players = self.state.seated_players().await;
+
+        // every seat is taken, the game gets its id
+        let game_number = self.state.game_count.get().saturating_add(1);
+        self.state.game_count.set(game_number);
+        let game_id = game_id(self.runtime.chain_id(), game_number);
+        self.state.game_id.set(game_id.clone());
+
+        // every player receives an empty hand until every secret is revealed
+        let reveal_data = PlayData {
+            game_id,
+            game_number,
+            p_one_id: players[0].id.clone(),
+            p_two_id: players[1].id.clone(),
+            game_state: Status::Started,
+            last_update: self.runtime.system_time(),
+            ..PlayData::default()
+        };
+
+        for player in players.iter() {
+            self.state.play_data.insert(&player.id, reveal_data.clone()).unwrap_or_else(|_| {
+                panic!("Failed to update Play Data for {:?} - {:?}", player.name, player.id);
             });
-            self.state.play_data.insert(&player_two.id, p2_data).unwrap_or_else(|_| {
-                panic!("Failed to update Play Data for {:?} - {:?}", player_two.name, player_two.id);
+        }
+    }
+
+    async fn update_reveal_phase(&mut self) {
+        let players = self.state.seated_players().await;
+
+        let mut reveals = Vec::new();
+        for player in players.iter() {
+            let reveal = self.state.reveals.get(&player.id).await
+                .unwrap_or_else(|_| { panic!("unable to get reveal"); }).unwrap_or_default();
+            reveals.push(reveal);
+        }
+
+        // deck seed is known once every player revealed their secret, combined in seat order
+        let mut deck_seed = None;
+        if reveals.iter().all(|reveal| !reveal.is_empty()) {
+            deck_seed = Some(combine_reveals(reveals));
+        }
+
+        let current_time = self.runtime.system_time();
+
+        // publish the deck seed so every player can verify it
+        for player in players.iter() {
+            let mut data = self.state.play_data.get(&player.id).await
+                .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+            data.deck_seed = deck_seed;
+            data.last_update = current_time;
+            self.state.play_data.insert(&player.id, data).unwrap_or_else(|_| {
+                panic!("Failed to update Play Data for {:?}", player.id);
             });
+        }
+
+        self.state.deck_seed.set(deck_seed);
+        self.state.game_state.set(GameState { status: Status::Started, last_update: current_time });
+
+        if deck_seed.is_none() {
+            return;
+        }
+
+        // deal
+        match *self.state.game_mode.get() {
+            GameMode::Duel => self.start_game().await,
+            GameMode::Dealer => self.start_dealer_game().await,
+        }
+    }
+
+    async fn forfeit_reveal(&mut self) {
+        // players who never revealed lose, the others share the game
+        let players = self.state.seated_players().await;
+        let mut winners = Vec::new();
+        for (seat, player) in players.iter().enumerate() {
+            if self.state.reveals.contains_key(&player.id).await.unwrap_or(false) {
+                winners.push(seat);
+            }
+        }
+
+        // the house reveals after the player, a player who never revealed loses to it
+        if winners.is_empty() && *self.state.game_mode.get() == GameMode::Dealer {
+            winners.push(1);
+        }
+        let winner = Self::winner_name(&players, &winners);
+        let timed_out = (0..players.len()).filter(|seat| !winners.contains(seat)).map(|seat| seat as u8).collect();
+        self.log_event(GameEvent::RevealTimeout { seats: timed_out }).await;
+
+        let current_time = self.runtime.system_time();
+
+        for player in players.iter() {
+            let mut data = self.state.play_data.get(&player.id).await
+                .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+            data.winner = winner.clone();
+            data.game_state = Status::Finish;
+            data.last_update = current_time;
+            data.player_id_turn = "".to_string();
+            self.state.play_data.insert(&player.id, data).unwrap_or_else(|_| {
+                panic!("Failed to update Play Data for {:?}", player.id);
+            });
+        }
+
+        self.state.game_state.set(GameState { status: Status::Finish, last_update: current_time });
+
+        self.finish_game(&players, &winners).await;
+    }
+
+    async fn forfeit_dealer_game(&mut self) {
+        if self.state.deck_seed.get().is_none() {
+            self.forfeit_reveal().await;
+            return;
+        }
+
+        let player_one = self.state.seated_players().await.remove(0);
+        let p1_data = self.state.play_data.get(&player_one.id).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+        let dealer_data = self.state.play_data.get(&DEALER_ID.to_string()).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+
+        // the house wins every hand the player left unplayed
+        self.log_event(GameEvent::IdleTimeout { seat: 0 }).await;
+        self.close_dealer_game(p1_data, dealer_data, LastAction::None, vec![1]).await;
+    }
+
+    async fn start_dealer_game(&mut self) {
+        // deal Player 1 and the house the same way as a two player game
+        self.start_game().await;
+
+        let player_one = self.state.seated_players().await.remove(0);
+        let p1_data = self.state.play_data.get(&player_one.id).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+        let dealer_data = self.state.play_data.get(&DEALER_ID.to_string()).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+
+        // game ends immediately if player or the house have blackjack (21) score
+        if p1_data.my_score == 21 || dealer_data.my_score == 21 {
+            self.finish_dealer_game(p1_data, dealer_data, LastAction::None).await;
+        }
+    }
+
+    async fn hit_against_dealer(&mut self, player_id: String) {
+        // load game data
+        let mut p1_data = self.state.play_data.get(&player_id).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+        let mut dealer_data = self.state.play_data.get(&DEALER_ID.to_string()).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+
+        // P1 active hand
+        let chosen_card = self.draw_card();
+        self.log_event(GameEvent::Hit { seat: 0, hand: p1_data.active_hand, card: chosen_card }).await;
+        let hand = &mut p1_data.my_hands[p1_data.active_hand as usize];
+        hand.hand.push(chosen_card);
+        dealer_data.opponent_card.push(chosen_card);
+        dealer_data.opponent_score = Hand::new(dealer_data.opponent_card.clone()).value();
+
+        // bust or 21 can't take any more card
+        if hand.hand.value() >= 21 {
+            hand.finished = true;
+        }
+
+        self.play_next_hand(p1_data, dealer_data, LastAction::Hit).await;
+    }
+
+    async fn stand_against_dealer(&mut self, player_id: String) {
+        // load game data
+        let mut p1_data = self.state.play_data.get(&player_id).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+        let dealer_data = self.state.play_data.get(&DEALER_ID.to_string()).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+
+        p1_data.my_hands[p1_data.active_hand as usize].finished = true;
+        self.log_event(GameEvent::Stand { seat: 0, hand: p1_data.active_hand }).await;
+
+        self.play_next_hand(p1_data, dealer_data, LastAction::Stand).await;
+    }
+
+    async fn double_down_against_dealer(&mut self, player_id: String) -> Result<(), BlackJackError> {
+        // load game data
+        let mut p1_data = self.state.play_data.get(&player_id).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+        let mut dealer_data = self.state.play_data.get(&DEALER_ID.to_string()).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+
+        // only a fresh hand can be doubled
+        let active_hand = p1_data.active_hand as usize;
+        if p1_data.my_hands[active_hand].hand.len() != 2 {
+            return Err(BlackJackError::CannotDoubleDown);
+        }
+
+        // exactly one more card, then the hand stands
+        let chosen_card = self.draw_card();
+        self.log_event(GameEvent::DoubleDown { seat: 0, hand: active_hand as u8, card: chosen_card }).await;
+        let hand = &mut p1_data.my_hands[active_hand];
+        hand.hand.push(chosen_card);
+        hand.doubled = true;
+        hand.finished = true;
+        dealer_data.opponent_card.push(chosen_card);
+        dealer_data.opponent_score = Hand::new(dealer_data.opponent_card.clone()).value();
+
+        self.play_next_hand(p1_data, dealer_data, LastAction::DoubleDown).await;
+        Ok(())
+    }
+
+    async fn split_against_dealer(&mut self, player_id: String) -> Result<(), BlackJackError> {
+        // load game data
+        let mut p1_data = self.state.play_data.get(&player_id).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+        let mut dealer_data = self.state.play_data.get(&DEALER_ID.to_string()).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+
+        // only a pair of the same rank can be split
+        let active_hand = p1_data.active_hand as usize;
+        let pair = p1_data.my_hands[active_hand].hand.clone();
+        if !pair.is_pair() || p1_data.my_hands.len() >= MAX_SPLIT_HANDS {
+            return Err(BlackJackError::CannotSplit);
+        }
+
+        // every card of the pair starts a new hand and receives a second card
+        let split_aces = pair.cards[0].is_ace();
+        let mut new_hands = Vec::new();
+        for card in pair.cards {
+            let chosen_card = self.draw_card();
+            let mut hand = PlayHand::new(vec![card, chosen_card]);
+
+            // split aces receive one card only
+            hand.finished = split_aces || hand.hand.value() == 21;
+            new_hands.push(hand);
+
+            dealer_data.opponent_card.push(chosen_card);
+            dealer_data.opponent_score = Hand::new(dealer_data.opponent_card.clone()).value();
+        }
+        let second_cards = new_hands.iter().map(|hand| hand.hand.cards[1]).collect();
+        self.log_event(GameEvent::Split { seat: 0, hand: active_hand as u8, cards: second_cards }).await;
+        p1_data.my_hands.splice(active_hand..=active_hand, new_hands);
+
+        self.play_next_hand(p1_data, dealer_data, LastAction::Split).await;
+        Ok(())
+    }
+
+    async fn surrender_against_dealer(&mut self, player_id: String) -> Result<(), BlackJackError> {
+        // load game data
+        let mut p1_data = self.state.play_data.get(&player_id).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+        let dealer_data = self.state.play_data.get(&DEALER_ID.to_string()).await
+            .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_else(|| { panic!("unable to get play data"); });
+
+        // late surrender, only as the first action of the game
+        if p1_data.last_action != LastAction::None || p1_data.my_hands.len() != 1 {
+            return Err(BlackJackError::CannotSurrender);
+        }
+
+        self.log_event(GameEvent::Surrender { seat: 0 }).await;
+        let hand = &mut p1_data.my_hands[0];
+        hand.surrendered = true;
+        hand.finished = true;
+
+        self.play_next_hand(p1_data, dealer_data, LastAction::Surrender).await;
+        Ok(())
+    }
+
+    async fn play_next_hand(&mut self, mut p1_data: PlayData, mut dealer_data: PlayData, last_action: LastAction) {
+        // keep the classic card fields in sync with the hand just played
+        let active_hand = p1_data.active_hand as usize;
+        p1_data.my_card = p1_data.my_hands[active_hand].hand.cards.clone();
+        p1_data.my_score = p1_data.my_hands[active_hand].hand.value();
+
+        // continue with the next unfinished hand
+        if let Some(next_hand) = p1_data.my_hands.iter().position(|hand| !hand.finished) {
+            let current_time = self.runtime.system_time();
 
-            // send message to leaderboard chain
-            self.send_game_finish_message(
-                player_one.name.clone(),
-                player_one.gid.clone(),
-                player_two.name.clone(),
-                player_two.gid.clone(),
-                winner,
-                winner_gid,
-            ).await;
-
-            // send room status update
-            self.send_room_status_update().await;
-        } else {
             // update data
-            p1_data.player_id_turn = next_turn.clone();
-            p1_data.last_action = LastAction::Hit;
+            p1_data.active_hand = next_hand as u8;
+            p1_data.my_card = p1_data.my_hands[next_hand].hand.cards.clone();
+            p1_data.my_score = p1_data.my_hands[next_hand].hand.value();
+            p1_data.last_action = last_action;
             p1_data.last_update = current_time;
-            p2_data.player_id_turn = next_turn;
-            p2_data.last_action = LastAction::Hit;
-            p2_data.last_update = current_time;
+            dealer_data.last_action = last_action;
+            dealer_data.last_update = current_time;
 
             // save data to state
+            let player_id = p1_data.p_one_id.clone();
             self.state.game_state.set(GameState { status: Status::Started, last_update: current_time });
-            self.state.play_data.insert(&player_one.id, p1_data).unwrap_or_else(|_| {
-                panic!("Failed to update Play Data for {:?} - {:?}", player_one.name, player_one.id);
+            self.state.play_data.insert(&player_id, p1_data).unwrap_or_else(|_| {
+                panic!("Failed to update Play Data for {:?}", player_id);
             });
-            self.state.play_data.insert(&player_two.id, p2_data).unwrap_or_else(|_| {
-                panic!("Failed to update Play Data for {:?} - {:?}", player_two.name, player_two.id);
+            self.state.play_data.insert(&DEALER_ID.to_string(), dealer_data).unwrap_or_else(|_| {
+                panic!("Failed to update Play Data for {:?}", DEALER_ID);
             });
+            return;
+        }
+
+        // reveal hole card, the house only draws when a hand is still in play
+        if p1_data.my_hands.iter().any(|hand| !hand.surrendered && !hand.hand.is_bust()) {
+            self.play_dealer_hand(&mut dealer_data).await;
         }
+        self.finish_dealer_game(p1_data, dealer_data, last_action).await;
     }
 
-    async fn start_game(&mut self, p2_id: String, p2_name: String) {
-        let mut new_decks = Vec::from(CARD_DECKS);
+    async fn play_dealer_hand(&mut self, dealer_data: &mut PlayData) {
+        // house keeps drawing until it reaches the stand threshold
+        while dealer_data.my_score < DEALER_STAND_THRESHOLD {
+            let chosen_card = self.draw_card();
+            self.log_event(GameEvent::DealerDraw { card: chosen_card }).await;
+            dealer_data.my_card.push(chosen_card);
+            dealer_data.my_score = Hand::new(dealer_data.my_card.clone()).value();
+        }
+        dealer_data.my_hands = vec![PlayHand::new(dealer_data.my_card.clone())];
+    }
 
-        let mut p1_card: Vec<u8> = Vec::new();
-        let mut p2_card: Vec<u8> = Vec::new();
-        let mut p1_card_for_opponent: Vec<u8> = Vec::new();
-        let mut p2_card_for_opponent: Vec<u8> = Vec::new();
+    async fn finish_dealer_game(&mut self, p1_data: PlayData, dealer_data: PlayData, last_action: LastAction) {
+        let dealer_score = dealer_data.my_score;
+
+        // settle every hand against the house, a doubled hand counts twice
+        let mut balance: i32 = 0;
+        for hand in p1_data.my_hands.iter() {
+            let weight = if hand.doubled { 2 } else { 1 };
+            let score = hand.hand.value();
+            if hand.surrendered || score > 21 || (dealer_score <= 21 && dealer_score > score) {
+                balance -= weight;
+            } else if dealer_score > 21 || score > dealer_score {
+                balance += weight;
+            }
+        }
 
-        let mut p1_score: u8 = 0;
-        let mut p2_score: u8 = 0;
-        let mut p1_score_for_opponent: u8 = 0;
-        let mut p2_score_for_opponent: u8 = 0;
+        let winners = match balance.cmp(&0) {
+            // House win
+            std::cmp::Ordering::Less => vec![1],
+            // Player 1 win
+            std::cmp::Ordering::Greater => vec![0],
+            // Draw
+            std::cmp::Ordering::Equal => vec![0, 1],
+        };
+        self.close_dealer_game(p1_data, dealer_data, last_action, winners).await;
+    }
 
-        let player_one = self.state.p1.get();
+    async fn close_dealer_game(&mut self, mut p1_data: PlayData, mut dealer_data: PlayData, last_action: LastAction, winners: Vec<usize>) {
+        let players = self.state.seated_players().await;
+        let player_one = players[0].clone();
+        let player_two = players[1].clone();
 
-        // P1 First Card
-        let mut ts = self.runtime.system_time().to_string();
-        let mut index = random_index(ts, new_decks.len() as u8, player_one.id.clone(), "f".to_string());
-        let mut chosen_card = new_decks.get(index as usize).unwrap_or_else(|| {
-            panic!("unable to draw card");
-        });
-        p1_card.push(*chosen_card);
-        p1_score = calculate_player_score(*chosen_card, &p1_card, p1_score);
-        p1_card_for_opponent.push(0);
-        new_decks.swap_remove(index as usize);
-
-        // P2 First Card
-        ts = self.runtime.system_time().to_string();
-        index = random_index(ts, new_decks.len() as u8, p2_id.clone(), "f".to_string());
-        chosen_card = new_decks.get(index as usize).unwrap_or_else(|| {
-            panic!("unable to draw card");
-        });
-        p2_card.push(*chosen_card);
-        p2_score = calculate_player_score(*chosen_card, &p2_card, p2_score);
-        p2_card_for_opponent.push(0);
-        new_decks.swap_remove(index as usize);
-
-        // P1 Second Card
-        ts = self.runtime.system_time().to_string();
-        index = random_index(ts, new_decks.len() as u8, player_one.id.clone(), "s".to_string());
-        chosen_card = new_decks.get(index as usize).unwrap_or_else(|| {
-            panic!("unable to draw card");
-        });
-        p1_card.push(*chosen_card);
-        p1_card_for_opponent.push(*chosen_card);
-        p1_score = calculate_player_score(*chosen_card, &p1_card, p1_score);
-        p1_score_for_opponent = calculate_player_score(*chosen_card, &p1_card_for_opponent, p1_score_for_opponent);
-        new_decks.swap_remove(index as usize);
-
-        // P2 Second Card
-        ts = self.runtime.system_time().to_string();
-        index = random_index(ts, new_decks.len() as u8, p2_id.clone(), "s".to_string());
-        chosen_card = new_decks.get(index as usize).unwrap_or_else(|| {
-            panic!("unable to draw card");
-        });
-        p2_card.push(*chosen_card);
-        p2_card_for_opponent.push(*chosen_card);
-        p2_score = calculate_player_score(*chosen_card, &p2_card, p2_score);
-        p2_score_for_opponent = calculate_player_score(*chosen_card, &p2_card_for_opponent, p2_score_for_opponent);
-        new_decks.swap_remove(index as usize);
+        let winner = Self::winner_name(&players, &winners);
 
         let current_time = self.runtime.system_time();
 
-        // save play data
-        self.state.play_data.insert(&player_one.id, PlayData {
-            p_one_id: player_one.id.clone(),
-            p_two_id: p2_id.clone(),
-            my_card: p1_card,
-            opponent_card: p2_card_for_opponent,
-            my_score: p1_score,
-            opponent_score: p2_score_for_opponent,
-            player_id_turn: player_one.id.clone(),
-            last_action: LastAction::None,
-            winner: String::from(""),
-            game_state: Status::Started,
-            last_update: current_time,
-        },
-        ).unwrap_or_else(|_| {
+        // update data
+        p1_data.winner = winner.clone();
+        p1_data.game_state = Status::Finish;
+        p1_data.last_action = last_action;
+        p1_data.last_update = current_time;
+        p1_data.player_id_turn = "".to_string();
+        p1_data.opponent_score = dealer_data.my_score;
+        p1_data.opponent_card = dealer_data.my_card.clone();
+
+        dealer_data.winner = winner.clone();
+        dealer_data.game_state = Status::Finish;
+        dealer_data.last_action = last_action;
+        dealer_data.last_update = current_time;
+        dealer_data.player_id_turn = "".to_string();
+        dealer_data.opponent_score = p1_data.my_score;
+        dealer_data.opponent_card = p1_data.my_hands.iter().flat_map(|hand| hand.hand.cards.clone()).collect();
+
+        // save data to state
+        self.state.game_state.set(GameState { status: Status::Finish, last_update: current_time });
+        self.state.play_data.insert(&player_one.id, p1_data).unwrap_or_else(|_| {
             panic!("Failed to update Play Data for {:?} - {:?}", player_one.name, player_one.id);
         });
-        self.state.play_data.insert(&p2_id, PlayData {
-            p_one_id: player_one.id.clone(),
-            p_two_id: p2_id.clone(),
-            my_card: p2_card,
-            opponent_card: p1_card_for_opponent,
-            my_score: p2_score,
-            opponent_score: p1_score_for_opponent,
-            player_id_turn: player_one.id.clone(),
-            last_action: LastAction::None,
-            winner: String::from(""),
-            game_state: Status::Started,
-            last_update: current_time,
-        },
-        ).unwrap_or_else(|_| {
-            panic!("Failed to update Play Data for {:?} - {:?}", p2_name, p2_id);
+        self.state.play_data.insert(&player_two.id, dealer_data).unwrap_or_else(|_| {
+            panic!("Failed to update Play Data for {:?} - {:?}", player_two.name, player_two.id);
         });
 
-        // save card deck
-        self.state.decks.set(new_decks);
+        self.finish_game(&players, &winners).await;
     }
 }
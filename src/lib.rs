@@ -1,19 +1,28 @@
-mod constants;
-mod random;
+// GraphQLMutationRoot turns every CardOperation field into a mutation argument
+#![allow(clippy::too_many_arguments)]
 
+pub mod constants;
+
+use std::fmt;
 use std::str::FromStr;
 use async_graphql::{Request, Response, scalar};
-use async_graphql_derive::{SimpleObject};
-use linera_sdk::base::{ChainId, ContractAbi, ServiceAbi, Timestamp};
+use async_graphql_derive::{ComplexObject, SimpleObject};
+use linera_sdk::base::{Amount, BcsHashable, ChainId, ContractAbi, CryptoHash, Owner, ServiceAbi, Timestamp};
+use linera_sdk::bcs;
 use linera_sdk::graphql::GraphQLMutationRoot;
+use linera_sdk::views::{CustomSerialize, ViewError};
 use serde::{Deserialize, Serialize};
-use crate::constants::{MILLENNIUM};
+use crate::constants::{
+    CARD_DECKS, DEALER_GID, DEFAULT_LOBBY_TIMEOUT, DEFAULT_MATCHMAKING_BAND, DEFAULT_MATCHMAKING_WIDENING, DEFAULT_WIN_RATE, MAX_WIN_RATE, DEFAULT_RATING, DEFAULT_TURN_TIMEOUT, MAX_LOBBY_TIMEOUT, MAX_TURN_TIMEOUT,
+    MIN_LOBBY_TIMEOUT, MIN_TURN_TIMEOUT, DEFAULT_SHOE_DECKS, DEFAULT_TABLE_SEATS, DEFAULT_SHOE_PENETRATION, MAX_SHOE_DECKS,
+    MAX_SHOE_PENETRATION, MILLENNIUM, MIN_SHOE_DECKS, MIN_SHOE_PENETRATION, RATING_K_FACTOR,
+};
 
 pub struct BlackJackAbi;
 
 impl ContractAbi for BlackJackAbi {
     type Operation = CardOperation;
-    type Response = ();
+    type Response = CardResponse;
 }
 
 impl ServiceAbi for BlackJackAbi {
@@ -27,29 +36,46 @@ impl ServiceAbi for BlackJackAbi {
 #[derive(Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct BlackJackParameters {
     pub leaderboard_chain_id: ChainId,
-    pub leaderboard_pass: String,
     pub room_status_chain_id: ChainId,
     pub analytics_chain_id: ChainId,
     pub player_status_chain_id: ChainId,
 }
 
+/// ------------------------------------------------------------------------------------------
+/// [BlackJackInstantiation]
+/// ------------------------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlackJackInstantiation {
+    /// owners allowed to manage Leaderboard and Analytics, defaults to the app creator
+    #[serde(default)]
+    pub admins: Vec<Owner>,
+    /// shoe used by every room chain, rooms keep the default until synced with Leaderboard chain
+    #[serde(default)]
+    pub shoe: ShoeSettings,
+    /// lobby and turn timeouts of every room chain, synced the same way as the shoe
+    #[serde(default)]
+    pub timeouts: TimeoutSettings,
+    /// win rate band used to pair players, sent along every match request by Leaderboard chain
+    #[serde(default)]
+    pub matchmaking: MatchmakingSettings,
+}
+
 /// ------------------------------------------------------------------------------------------
 /// [BlackJackMessage]
 /// ------------------------------------------------------------------------------------------
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum BlackJackMessage {
     GameResult {
-        p1: String,
-        p1gid: String,
-        p2: String,
-        p2gid: String,
+        game_id: String,
+        players: Vec<SeatResult>,
         winner: String,
-        winner_gid: String,
         time: Timestamp,
+        payout: Amount,
+        reason: GameEndReason,
     },
     RoomUpdate {
         id: ChainId,
-        status: Insight,
+        status: Box<Insight>,
     },
     Analytic {
         version: String,
@@ -59,9 +85,49 @@ pub enum BlackJackMessage {
         gid: String,
     },
     PlayerFinish {
-        p1: String,
-        p2: String,
+        players: Vec<String>,
+    },
+    ResetAnalytics,
+    MatchLookup {
+        name: String,
+        gid: String,
+        chain_id: ChainId,
+        stake: Amount,
+    },
+    MatchRequest {
+        name: String,
+        gid: String,
+        chain_id: ChainId,
+        stake: Amount,
+        win_rate: u32,
+        settings: MatchmakingSettings,
+    },
+    MatchFound {
+        name: String,
+        room: ChainId,
+        stake: Amount,
+    },
+    /// house asks the Room Status chain for its part of the deck seed once the player revealed
+    RequestHouseSecret {
+        game_id: String,
+    },
+    HouseSecret {
+        game_id: String,
+        secret: String,
     },
+    RequestTableSettings,
+    TableSettings {
+        shoe: ShoeSettings,
+        timeouts: TimeoutSettings,
+    },
+}
+scalar!(BlackJackMessage);
+
+/// Tracked message that bounced back to the room chain, kept until it's resent
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+pub struct OutboxEntry {
+    pub time: Timestamp,
+    pub message: BlackJackMessage,
 }
 
 /// ------------------------------------------------------------------------------------------
@@ -74,26 +140,151 @@ pub enum CardOperation {
         player_name: String,
         version: String,
         gid: String,
+        commitment: CryptoHash,
+        stake: Option<Amount>,
+        seats: Option<u8>,
     },
-    Action {
+    JoinDealer {
         player_id: String,
+        player_name: String,
+        version: String,
+        gid: String,
+        commitment: CryptoHash,
+    },
+    Reveal {
+        secret: String,
+    },
+    Action {
         action: u8,
     },
-    IdleActionCheck {
-        player_id: String,
+    IdleActionCheck,
+    LeaveTable,
+    StartLeaderBoard,
+    StopLeaderBoard,
+    ResetLeaderBoard,
+    ResetAnalytics,
+    AddAdmin {
+        owner: Owner,
     },
-    StartLeaderBoard {
-        p: String,
+    RemoveAdmin {
+        owner: Owner,
     },
-    StopLeaderBoard {
-        p: String,
+    CreateSeason {
+        name: String,
+        start: Timestamp,
+        end: Timestamp,
+    },
+    FindMatch {
+        player_name: String,
+        gid: String,
+        stake: Option<Amount>,
     },
-    ResetLeaderBoard {
-        p: String,
+    ResendBouncedMessages,
+    DiscardPendingResults {
+        game_ids: Vec<String>,
     },
-    ResetAnalytics {
-        p: String,
+    DrainPendingResults {
+        max: u32,
     },
+    RegisterRoom,
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [CardResponse]
+/// ------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub enum CardResponse {
+    /// operation succeeded and has no game data to return
+    Ok,
+    /// operation succeeded, with the invoker's updated game data
+    PlayData(Box<PlayData>),
+    /// operation was rejected and the state is unchanged
+    Error(BlackJackError),
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [BlackJackError]
+/// ------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, Deserialize, Eq, PartialEq, Serialize)]
+pub enum BlackJackError {
+    LeaderboardChainNotAllowed,
+    RoomStatusChainNotAllowed,
+    AnalyticsChainNotAllowed,
+    PlayerStatusChainNotAllowed,
+    Unauthenticated,
+    Unauthorized,
+    LastAdmin,
+    ReservedPlayerName,
+    ReservedPlayerId,
+    SimilarPlayer,
+    GameAlreadyStarted,
+    TableInUse,
+    TableNotWaiting,
+    LobbyNotTimedOut,
+    GameNotStarted,
+    PlayerNotExist,
+    NotYourTurn,
+    IdleCheckByCurrentPlayer,
+    IdleCheckTooEarly,
+    ActionNotRecognized,
+    ActionNotAllowed,
+    CannotDoubleDown,
+    CannotSplit,
+    CannotSurrender,
+    StakeMismatch,
+    InsufficientBalance,
+    InvalidSeatCount,
+    CardsAlreadyDealt,
+    WaitingForReveal,
+    EmptySecret,
+    SecretAlreadyRevealed,
+    SecretMismatch,
+    SecretNotRevealed,
+    InvalidSeason,
+    LeaderboardClosed,
+}
+
+impl fmt::Display for BlackJackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            BlackJackError::LeaderboardChainNotAllowed => "Leaderboard chain are not allowed to play",
+            BlackJackError::RoomStatusChainNotAllowed => "Room status chain are not allowed to play",
+            BlackJackError::AnalyticsChainNotAllowed => "Analytics chain are not allowed to play",
+            BlackJackError::PlayerStatusChainNotAllowed => "Player Status chain are not allowed to play",
+            BlackJackError::Unauthenticated => "operation must be signed by the player",
+            BlackJackError::Unauthorized => "You are not authorized to execute Leaderboard and/or Analytics operation",
+            BlackJackError::LastAdmin => "unable to remove the last admin",
+            BlackJackError::ReservedPlayerName => "player name is reserved for the house",
+            BlackJackError::ReservedPlayerId => "player ID and gid are reserved for the house",
+            BlackJackError::SimilarPlayer => "unable to start, both players have similar name or ID",
+            BlackJackError::GameAlreadyStarted => "blackjack have started",
+            BlackJackError::TableInUse => "table is in use",
+            BlackJackError::TableNotWaiting => "table is not waiting for players",
+            BlackJackError::LobbyNotTimedOut => "too early to leave the table",
+            BlackJackError::GameNotStarted => "game not started yet",
+            BlackJackError::PlayerNotExist => "player not exist",
+            BlackJackError::NotYourTurn => "not your turn",
+            BlackJackError::IdleCheckByCurrentPlayer => "current player can't do idle action check",
+            BlackJackError::IdleCheckTooEarly => "too early for idle action check",
+            BlackJackError::ActionNotRecognized => "action not recognized",
+            BlackJackError::ActionNotAllowed => "action is only available against the dealer",
+            BlackJackError::CannotDoubleDown => "double down needs a hand with two cards",
+            BlackJackError::CannotSplit => "split needs a hand with a pair and a free hand slot",
+            BlackJackError::CannotSurrender => "surrender is only allowed as the first action",
+            BlackJackError::StakeMismatch => "stake must match the stake of Player 1",
+            BlackJackError::InsufficientBalance => "not enough balance for the stake",
+            BlackJackError::InvalidSeatCount => "a table has 2 to 7 seats",
+            BlackJackError::CardsAlreadyDealt => "cards have been dealt",
+            BlackJackError::WaitingForReveal => "waiting for players to reveal",
+            BlackJackError::EmptySecret => "secret can't be empty",
+            BlackJackError::SecretAlreadyRevealed => "secret already revealed",
+            BlackJackError::SecretMismatch => "secret does not match commitment",
+            BlackJackError::SecretNotRevealed => "reveal your secret first",
+            BlackJackError::InvalidSeason => "season must end in the future, after it starts, and can't overlap another season",
+            BlackJackError::LeaderboardClosed => "Leaderboard is closed at the moment",
+        };
+        write!(f, "{}", message)
+    }
 }
 
 /// ------------------------------------------------------------------------------------------
@@ -118,17 +309,26 @@ pub struct Player {
     pub win: u32,
     pub lose: u32,
     pub play: u32,
+    /// signer that owns this seat, `id` and `name` are display only
+    pub owner: Option<Owner>,
+    /// tokens held in escrow on the room chain until the game is settled
+    pub stake: Amount,
+    /// Elo rating, only kept on the Leaderboard chain
+    pub rating: u32,
 }
 
 impl Player {
     pub fn new(name: String) -> Self {
         Player {
             id: String::from(""),
+            owner: None,
+            stake: Amount::ZERO,
             name,
             gid: String::from(""),
             win: 0,
             lose: 0,
             play: 1,
+            rating: DEFAULT_RATING,
         }
     }
 }
@@ -164,7 +364,6 @@ impl Default for GameState {
 /// ------------------------------------------------------------------------------------------
 /// [Status]
 /// ------------------------------------------------------------------------------------------
-scalar!(Status);
 #[derive(Debug, Clone, Copy, Deserialize, Eq, Ord, PartialOrd, PartialEq, Serialize)]
 pub enum Status {
     Idle,
@@ -172,6 +371,20 @@ pub enum Status {
     Started,
     Finish,
 }
+scalar!(Status);
+
+/// ------------------------------------------------------------------------------------------
+/// [GameMode]
+/// ------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, Default, Deserialize, Eq, Ord, PartialOrd, PartialEq, Serialize)]
+pub enum GameMode {
+    /// two players compete head-to-head
+    #[default]
+    Duel,
+    /// a single player competes against the house hand
+    Dealer,
+}
+scalar!(GameMode);
 
 /// ------------------------------------------------------------------------------------------
 /// [Insight]
@@ -189,18 +402,24 @@ pub enum Status {
 )]
 pub struct Insight {
     pub id: ChainId,
+    /// game being played on the room chain, empty while waiting for players
+    pub game_id: String,
+    pub game_mode: GameMode,
     pub game_state: GameState,
-    pub p_one: Player,
-    pub p_two: Player,
+    pub seat_count: u8,
+    /// seated players in seat order
+    pub seats: Vec<Player>,
 }
 
 impl Default for Insight {
     fn default() -> Self {
         Self {
             id: ChainId::from_str("e4854ab09513d0e0b62497a5e190a074ff161c6c39e4dfa07dc5e2c0ee73d284").unwrap(),
+            game_id: "".to_string(),
+            game_mode: GameMode::Duel,
             game_state: GameState::default(),
-            p_one: Player::default(),
-            p_two: Player::default(),
+            seat_count: DEFAULT_TABLE_SEATS,
+            seats: vec![],
         }
     }
 }
@@ -220,51 +439,135 @@ impl Default for Insight {
     SimpleObject
 )]
 pub struct PlayData {
+    /// unique across every room chain, see [game_id]
+    pub game_id: String,
+    /// game counter of the room chain, used to shuffle the shoe
+    pub game_number: u64,
     pub p_one_id: String,
     pub p_two_id: String,
-    pub my_card: Vec<u8>,
-    pub opponent_card: Vec<u8>,
+    pub my_card: Vec<Card>,
+    pub opponent_card: Vec<Card>,
     pub my_score: u8,
     pub opponent_score: u8,
+    /// every hand of the player, `my_card` and `my_score` mirror the active one
+    pub my_hands: Vec<PlayHand>,
+    pub active_hand: u8,
     pub player_id_turn: String,
     pub last_action: LastAction,
     pub winner: String,
     pub game_state: Status,
     pub last_update: Timestamp,
+    pub deck_seed: Option<CryptoHash>,
+    /// every seat as seen by other players, filled when the play data is read
+    pub table: Vec<TableSeat>,
 }
 
 impl Default for PlayData {
     fn default() -> Self {
         Self {
+            game_id: "".to_string(),
+            game_number: 0,
             p_one_id: "".to_string(),
             p_two_id: "".to_string(),
             my_card: vec![],
             opponent_card: vec![],
             my_score: 0,
             opponent_score: 0,
+            my_hands: vec![],
+            active_hand: 0,
             player_id_turn: "".to_string(),
             last_action: LastAction::None,
             winner: "".to_string(),
             game_state: Status::Idle,
             last_update: Timestamp::from(MILLENNIUM),
+            table: vec![],
+            deck_seed: None,
+        }
+    }
+}
+
+impl PlayData {
+    /// Copy of the play data with the hole card face down, the way the table shows it before the game finish.
+    ///
+    /// Hole cards are not secret, the reveals in the room chain blocks give away the deck seed.
+    pub fn face_down(&self) -> PlayData {
+        let my_card = Hand::new(self.my_card.clone()).face_up().cards;
+
+        let mut my_hands = self.my_hands.clone();
+        if let Some(hole_card) = my_hands.first_mut().and_then(|hand| hand.hand.cards.first_mut()) {
+            *hole_card = Card::HIDDEN;
+        }
+
+        PlayData {
+            my_score: Hand::new(my_card.clone()).value(),
+            my_card,
+            my_hands,
+            ..self.clone()
         }
     }
 }
 
+/// Game id is the room chain id and the game counter of that chain, so it's unique across rooms
+pub fn game_id(chain_id: ChainId, game_number: u64) -> String {
+    format!("{}-{}", chain_id, game_number)
+}
+
 /// ------------------------------------------------------------------------------------------
-/// [LastAction]
+/// [TableSeat]
 /// ------------------------------------------------------------------------------------------
-scalar!(LastAction);
-#[derive(Debug, Clone, Copy, Deserialize, Eq, Ord, PartialOrd, PartialEq, Serialize)]
-pub enum LastAction {
-    None,
-    Stand,
-    Hit,
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct TableSeat {
+    pub seat: u8,
+    pub player_id: String,
+    pub name: String,
+    pub stake: Amount,
+    /// hole card stays hidden until the game finish
+    pub cards: Vec<Card>,
+    pub score: u8,
+    pub commit: Option<CryptoHash>,
+    pub revealed: bool,
+    /// secret behind `commit`, published once the game finish
+    pub reveal: String,
 }
 
 /// ------------------------------------------------------------------------------------------
-/// [History]
+/// [SeatResult]
 /// ------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, Deserialize, Eq, Ord, PartialOrd, PartialEq, Serialize)]
+pub enum SeatOutcome {
+    Win,
+    Lose,
+    Draw,
+}
+scalar!(SeatOutcome);
+
+impl SeatOutcome {
+    /// score against another seat of the same game, 1 for beating it and 0.5 for a tie
+    fn score_against(&self, other: SeatOutcome) -> f64 {
+        let rank = |outcome: SeatOutcome| match outcome {
+            SeatOutcome::Win => 2,
+            SeatOutcome::Draw => 1,
+            SeatOutcome::Lose => 0,
+        };
+        match rank(*self).cmp(&rank(other)) {
+            std::cmp::Ordering::Greater => 1.0,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Less => 0.0,
+        }
+    }
+}
+
 #[derive(
     Debug,
     Clone,
@@ -276,15 +579,66 @@ pub enum LastAction {
     Serialize,
     SimpleObject
 )]
-pub struct History {
-    pub p1: String,
-    pub p2: String,
-    pub winner: String,
-    pub time: Timestamp,
+pub struct SeatResult {
+    pub seat: u8,
+    pub name: String,
+    pub gid: String,
+    pub outcome: SeatOutcome,
+    /// best hand value of the seat, see [SeatResult::best_score]
+    pub score: u8,
+    /// every final hand of the seat, more than one after a split
+    pub hands: Vec<Hand>,
+    /// cards drawn after the deal by hitting, doubling down and splitting, dealer draws count for the house seat
+    pub hits: u8,
+    /// seat lost after letting its turn or the reveal time out
+    pub forfeit: bool,
+}
+
+impl SeatResult {
+    /// seat of the house in a game against the dealer, kept out of every leaderboard
+    pub fn is_house(&self) -> bool {
+        self.gid == DEALER_GID
+    }
+
+    /// highest hand value that is not bust, or the lowest one when every hand went over 21
+    pub fn best_score(hands: &[Hand]) -> u8 {
+        let values = hands.iter().map(|hand| hand.value());
+        values.clone().filter(|&value| value <= 21).max()
+            .or_else(|| values.min())
+            .unwrap_or(0)
+    }
+
+    /// natural blackjack, a split hand worth 21 doesn't count
+    pub fn is_blackjack(&self) -> bool {
+        self.hands.len() == 1 && self.hands[0].is_blackjack()
+    }
+
+    /// any hand of the seat went over 21
+    pub fn is_bust(&self) -> bool {
+        self.hands.iter().any(|hand| hand.is_bust())
+    }
 }
 
+/// Why a game ended, decided on the room chain when the game finish
+#[derive(Debug, Clone, Copy, Deserialize, Eq, Ord, PartialOrd, PartialEq, Serialize)]
+pub enum GameEndReason {
+    /// the winning seat has a natural blackjack
+    Blackjack,
+    /// the winning seat drew to exactly 21
+    TwentyOne,
+    /// every losing seat went over 21
+    Bust,
+    /// every seat still in the game stood and the best score won
+    StandOff,
+    /// a losing seat let its turn or the reveal time out
+    IdleForfeit,
+    /// more than one seat shared the game
+    Draw,
+}
+scalar!(GameEndReason);
+
 /// ------------------------------------------------------------------------------------------
-/// [Leaderboard]
+/// [PlayHand]
 /// ------------------------------------------------------------------------------------------
 #[derive(
     Debug,
@@ -298,64 +652,130 @@ pub struct History {
     Serialize,
     SimpleObject
 )]
-pub struct Leaderboard {
-    pub rank: Vec<Player>,
-    pub count: u32,
+pub struct PlayHand {
+    pub hand: Hand,
+    pub doubled: bool,
+    pub surrendered: bool,
+    pub finished: bool,
+}
+
+impl PlayHand {
+    pub fn new(cards: Vec<Card>) -> Self {
+        PlayHand {
+            hand: Hand::new(cards),
+            doubled: false,
+            surrendered: false,
+            finished: false,
+        }
+    }
 }
 
-impl Leaderboard {
-    pub fn update_player(&mut self, player_name: &String, winner_name: &String) {
-        let player_draw = String::from("");
+/// ------------------------------------------------------------------------------------------
+/// [Card]
+/// ------------------------------------------------------------------------------------------
+/// Serialized as the original 1..=52 value, 0 is a face down card.
+///
+/// Spades 1..=13, Hearts 14..=26, Diamonds 27..=39, Clubs 40..=52,
+/// and in every suit 1 = Ace, 11 = Jack, 12 = Queen, 13 = King.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialOrd, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Card(pub u8);
+scalar!(Card);
+
+impl Card {
+    pub const HIDDEN: Card = Card(0);
 
-        let is_draw = winner_name.eq(&player_draw);
-        let is_player_win = !is_draw && winner_name.eq(player_name);
-        let is_player_lose = !is_draw && winner_name.ne(player_name);
+    pub fn new(rank: Rank, suit: Suit) -> Self {
+        Card(suit as u8 * 13 + rank as u8)
+    }
 
-        if let Some(player) = self.rank.iter_mut().find(|p| p.name == *player_name) {
-            player.play = player.play.saturating_add(1);
+    pub fn is_hidden(&self) -> bool {
+        !(1..=52).contains(&self.0)
+    }
 
-            if is_player_win {
-                // Player Win
-                player.win = player.win.saturating_add(1);
-            } else if is_player_lose {
-                // Player Lose
-                player.lose = player.lose.saturating_add(1);
-            }
-        } else {
-            let mut new_player = Player::new(player_name.clone());
-
-            if is_player_win {
-                // Player Win
-                new_player.win = new_player.win.saturating_add(1);
-            } else if is_player_lose {
-                // Player Lose
-                new_player.lose = new_player.lose.saturating_add(1);
-            }
+    pub fn rank(&self) -> Option<Rank> {
+        if self.is_hidden() {
+            return None;
+        }
+        Rank::from_index((self.0 - 1) % 13 + 1)
+    }
 
-            self.rank.push(new_player);
+    pub fn suit(&self) -> Option<Suit> {
+        if self.is_hidden() {
+            return None;
         }
+        Suit::from_index((self.0 - 1) / 13)
     }
 
-    pub fn sort_rank(&mut self) {
-        // Sort by wins (desc), then losses (asc)
-        // Compare win counts -> cmp(&a.win)
-        // Compare lose counts -> then(a.lose.cmp(&b.lose))
-        self.rank.sort_by(|a, b| {
-            b.win.cmp(&a.win).then(a.lose.cmp(&b.lose))
-        });
+    pub fn is_ace(&self) -> bool {
+        self.rank() == Some(Rank::Ace)
+    }
+
+    /// hard value of the card, an Ace counts as 1 and a face down card as 0
+    pub fn value(&self) -> u8 {
+        self.rank().map(|rank| rank.value()).unwrap_or(0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialOrd, PartialEq, Serialize)]
+pub enum Rank {
+    Ace = 1,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+}
+scalar!(Rank);
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Ace, Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six, Rank::Seven,
+        Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King,
+    ];
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(index.checked_sub(1)? as usize).copied()
+    }
+
+    /// 10, Jack, Queen and King are worth 10, an Ace is worth 1 or 11
+    pub fn value(&self) -> u8 {
+        (*self as u8).min(10)
     }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialOrd, PartialEq, Serialize)]
+pub enum Suit {
+    Spades,
+    Hearts,
+    Diamonds,
+    Clubs,
+}
+scalar!(Suit);
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
 
-    pub fn update_count(&mut self) {
-        self.count = self.count.saturating_add(1);
+    pub fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
     }
 }
 
 /// ------------------------------------------------------------------------------------------
-/// [VersionAnalytics]
+/// [Hand]
 /// ------------------------------------------------------------------------------------------
+/// Serialized as the list of its cards, face down cards are not counted.
 #[derive(
-    Debug,
     Clone,
+    Debug,
+    Default,
     Deserialize,
     Eq,
     Ord,
@@ -364,23 +784,102 @@ impl Leaderboard {
     Serialize,
     SimpleObject
 )]
-pub struct VersionAnalytics {
-    pub v: String,
-    pub c: u32,
+#[graphql(complex)]
+#[serde(transparent)]
+pub struct Hand {
+    pub cards: Vec<Card>,
 }
 
-impl Default for VersionAnalytics {
-    fn default() -> Self {
-        Self { v: "".to_string(), c: 0 }
+#[ComplexObject]
+impl Hand {
+    #[graphql(name = "value")]
+    async fn graphql_value(&self) -> u8 {
+        self.value()
+    }
+
+    #[graphql(name = "isSoft")]
+    async fn graphql_is_soft(&self) -> bool {
+        self.is_soft()
+    }
+
+    #[graphql(name = "isBlackjack")]
+    async fn graphql_is_blackjack(&self) -> bool {
+        self.is_blackjack()
+    }
+
+    #[graphql(name = "isBust")]
+    async fn graphql_is_bust(&self) -> bool {
+        self.is_bust()
+    }
+}
+
+impl Hand {
+    pub fn new(cards: Vec<Card>) -> Self {
+        Hand { cards }
+    }
+
+    pub fn push(&mut self, card: Card) {
+        self.cards.push(card);
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    fn hard_value(&self) -> u8 {
+        self.cards.iter().fold(0u8, |total, card| total.saturating_add(card.value()))
+    }
+
+    /// one Ace counts as 11 whenever that doesn't bust the hand
+    pub fn is_soft(&self) -> bool {
+        self.cards.iter().any(|card| card.is_ace()) && self.hard_value() + 10 <= 21
+    }
+
+    pub fn value(&self) -> u8 {
+        if self.is_soft() {
+            self.hard_value() + 10
+        } else {
+            self.hard_value()
+        }
+    }
+
+    /// 21 with the first two cards
+    pub fn is_blackjack(&self) -> bool {
+        self.cards.len() == 2 && self.value() == 21
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.value() > 21
+    }
+
+    /// the hand as seen by other players, the first card is the hole card
+    pub fn face_up(&self) -> Hand {
+        let mut cards = self.cards.clone();
+        if let Some(hole_card) = cards.first_mut() {
+            *hole_card = Card::HIDDEN;
+        }
+        Hand { cards }
+    }
+
+    /// a pair of the same rank that can be split into two hands
+    pub fn is_pair(&self) -> bool {
+        self.cards.len() == 2 && self.cards[0].rank().is_some() && self.cards[0].rank() == self.cards[1].rank()
     }
 }
 
 /// ------------------------------------------------------------------------------------------
-/// [GidLeaderboard]
+/// [ShoeCard]
 /// ------------------------------------------------------------------------------------------
+/// A card in the shoe, `face` is the card used for hands and scores
+/// and `deck` tells apart identical faces coming from different decks.
 #[derive(
-    Debug,
     Clone,
+    Copy,
+    Debug,
     Default,
     Deserialize,
     Eq,
@@ -390,45 +889,728 @@ impl Default for VersionAnalytics {
     Serialize,
     SimpleObject
 )]
-pub struct GidLeaderboard {
-    pub gid: Vec<Player>,
-    pub count: u32,
+pub struct ShoeCard {
+    pub deck: u8,
+    pub face: Card,
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [ShoeSettings]
+/// ------------------------------------------------------------------------------------------
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct ShoeSettings {
+    /// number of 52 card decks in the shoe
+    pub decks: u8,
+    /// percentage of the shoe dealt before the cut card triggers a reshuffle
+    pub penetration: u8,
+}
+
+impl Default for ShoeSettings {
+    fn default() -> Self {
+        ShoeSettings {
+            decks: DEFAULT_SHOE_DECKS,
+            penetration: DEFAULT_SHOE_PENETRATION,
+        }
+    }
 }
 
-impl GidLeaderboard {
-    pub fn update_player(&mut self, player_gid: &String, winner_name: &String) {
-        let player_draw = String::from("");
+impl ShoeSettings {
+    pub fn is_valid(&self) -> bool {
+        (MIN_SHOE_DECKS..=MAX_SHOE_DECKS).contains(&self.decks)
+            && (MIN_SHOE_PENETRATION..=MAX_SHOE_PENETRATION).contains(&self.penetration)
+    }
 
-        let is_draw = winner_name.eq(&player_draw);
-        let is_player_win = !is_draw && winner_name.eq(player_gid);
-        let is_player_lose = !is_draw && winner_name.ne(player_gid);
+    pub fn shoe_size(&self) -> usize {
+        self.decks as usize * CARD_DECKS.len()
+    }
 
-        if let Some(player) = self.gid.iter_mut().find(|p| p.name == *player_gid) {
-            player.play = player.play.saturating_add(1);
+    /// remaining cards at which the cut card is reached
+    pub fn cut_card(&self) -> usize {
+        self.shoe_size() * (100 - self.penetration as usize) / 100
+    }
 
-            if is_player_win {
-                // Player Win
-                player.win = player.win.saturating_add(1);
-            } else if is_player_lose {
-                // Player Lose
-                player.lose = player.lose.saturating_add(1);
-            }
-        } else {
-            let mut new_player = Player::new(player_gid.clone());
-
-            if is_player_win {
-                // Player Win
-                new_player.win = new_player.win.saturating_add(1);
-            } else if is_player_lose {
-                // Player Lose
-                new_player.lose = new_player.lose.saturating_add(1);
-            }
+    pub fn new_shoe(&self) -> Vec<ShoeCard> {
+        (0..self.decks)
+            .flat_map(|deck| CARD_DECKS.iter().map(move |&face| ShoeCard { deck, face: Card(face) }))
+            .collect()
+    }
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [TimeoutSettings]
+/// ------------------------------------------------------------------------------------------
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct TimeoutSettings {
+    /// microseconds before a waiting table resets or a stalled game can be taken over
+    pub lobby: u64,
+    /// microseconds a player can idle before the idle action check stands for them
+    pub turn: u64,
+}
 
-            self.gid.push(new_player);
+impl Default for TimeoutSettings {
+    fn default() -> Self {
+        TimeoutSettings {
+            lobby: DEFAULT_LOBBY_TIMEOUT,
+            turn: DEFAULT_TURN_TIMEOUT,
         }
     }
 }
 
+impl TimeoutSettings {
+    pub fn is_valid(&self) -> bool {
+        (MIN_LOBBY_TIMEOUT..=MAX_LOBBY_TIMEOUT).contains(&self.lobby)
+            && (MIN_TURN_TIMEOUT..=MAX_TURN_TIMEOUT).contains(&self.turn)
+            && self.lobby > self.turn
+    }
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [CommitReveal]
+/// ------------------------------------------------------------------------------------------
+/// Each player commits to `commit_secret(secret)` on Join and reveals `secret` before the deal.
+/// The deck seed is the hash of every reveal in seat order (Player 1 first). The deck of game
+/// number `game_number` shuffles the cards left in the shoe with Fisher–Yates. Step `i` swaps card `i`
+/// with card `j`, where `j` is the first 8 bytes of the hash of `ShuffleStep { seed: shuffle_seed, step: i }`
+/// read as a little endian u64, modulo `i + 1`, and `shuffle_seed` is the hash of `ShuffleSeed { seed, game_number }`.
+/// Cards are drawn from the back of the shoe. A shoe that runs out in the middle of a game is
+/// refilled without the cards dealt in that game and shuffled the same way.
+/// Anyone holding the reveals can replay every draw of the game.
+///
+/// Against the house, the house secret is drawn on the Room Status chain only after the player's
+/// commitment is on the room chain, so the player can't choose a secret that fits it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SecretReveal {
+    pub secret: String,
+}
+
+impl BcsHashable for SecretReveal {}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeckSeed {
+    pub reveals: Vec<String>,
+}
+
+impl BcsHashable for DeckSeed {}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ShuffleSeed {
+    pub seed: CryptoHash,
+    pub game_number: u64,
+}
+
+impl BcsHashable for ShuffleSeed {}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ShuffleStep {
+    pub seed: CryptoHash,
+    pub step: u64,
+}
+
+impl BcsHashable for ShuffleStep {}
+
+/// Entropy of the Room Status chain, chained from every house secret it handed out before
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HouseEntropy {
+    pub previous: Option<CryptoHash>,
+    pub room: ChainId,
+    pub game_id: String,
+    pub block_height: u64,
+    pub time: u64,
+}
+
+impl BcsHashable for HouseEntropy {}
+
+pub fn commit_secret(secret: &str) -> CryptoHash {
+    CryptoHash::new(&SecretReveal { secret: secret.to_string() })
+}
+
+pub fn combine_reveals(reveals: Vec<String>) -> CryptoHash {
+    CryptoHash::new(&DeckSeed { reveals })
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [LastAction]
+/// ------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, Deserialize, Eq, Ord, PartialOrd, PartialEq, Serialize)]
+pub enum LastAction {
+    None,
+    Stand,
+    Hit,
+    DoubleDown,
+    Split,
+    Surrender,
+}
+scalar!(LastAction);
+
+/// ------------------------------------------------------------------------------------------
+/// [GameEvent]
+/// ------------------------------------------------------------------------------------------
+/// One step of a game on the room chain, `seat` and `hand` are indexes in seat and hand order
+#[derive(Debug, Clone, Deserialize, Eq, Ord, PartialOrd, PartialEq, Serialize)]
+pub enum GameEvent {
+    /// first card face down, second card face up
+    Deal { seat: u8, cards: Vec<Card> },
+    Hit { seat: u8, hand: u8, card: Card },
+    Stand { seat: u8, hand: u8 },
+    DoubleDown { seat: u8, hand: u8, card: Card },
+    /// second card of each new hand
+    Split { seat: u8, hand: u8, cards: Vec<Card> },
+    Surrender { seat: u8 },
+    DealerDraw { card: Card },
+    /// idle action check stands on behalf of the player on turn
+    IdleTimeout { seat: u8 },
+    /// seats that never revealed their secret
+    RevealTimeout { seats: Vec<u8> },
+    Settlement { winners: Vec<u8>, payout: Amount },
+}
+scalar!(GameEvent);
+
+impl GameEvent {
+    /// hole card of a dealt hand is face down while the game is in progress
+    pub fn face_down(&self) -> GameEvent {
+        match self {
+            GameEvent::Deal { seat, cards } => GameEvent::Deal { seat: *seat, cards: Hand::new(cards.clone()).face_up().cards },
+            event => event.clone(),
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct GameLogEntry {
+    pub time: Timestamp,
+    pub event: GameEvent,
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [SpectatorUpdate]
+/// ------------------------------------------------------------------------------------------
+/// Everything a spectator needs after a new block, hole cards stay hidden until the game finish
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct SpectatorUpdate {
+    /// height of the next block of the room chain, changes whenever the room state may have changed
+    pub block_height: u64,
+    pub insight: Insight,
+    pub play_data: PlayData,
+    /// events of the current game from the requested index on, every event after a new game started
+    pub events: Vec<GameLogEntry>,
+    /// index to request the next events from, together with the game id of `insight`
+    pub next_event: u32,
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [History]
+/// ------------------------------------------------------------------------------------------
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct History {
+    pub game_id: String,
+    pub players: Vec<SeatResult>,
+    pub winner: String,
+    pub time: Timestamp,
+    /// pot paid to the winning seats, zero when every seat draws or for a game without stakes
+    pub payout: Amount,
+    pub reason: GameEndReason,
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [Leaderboard]
+/// ------------------------------------------------------------------------------------------
+/// One page of the leaderboard, players are stored by name on the Leaderboard chain
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct Leaderboard {
+    pub rank: Vec<Player>,
+    /// games recorded on the leaderboard
+    pub count: u32,
+    /// players matching the query, across every page
+    pub total: u32,
+}
+
+impl Player {
+    /// wins over games played, in basis points
+    pub fn win_rate(&self) -> u32 {
+        if self.play == 0 {
+            return DEFAULT_WIN_RATE;
+        }
+        (self.win.min(self.play) as u64 * MAX_WIN_RATE as u64 / self.play as u64) as u32
+    }
+
+    /// Leaderboard entry after one more game, a new entry counts the game as its first play
+    pub fn record_game(entry: Option<Player>, name: &str, outcome: SeatOutcome) -> Player {
+        let is_player_win = outcome == SeatOutcome::Win;
+        let is_player_lose = outcome == SeatOutcome::Lose;
+
+        let mut player = match entry {
+            Some(mut player) => {
+                player.play = player.play.saturating_add(1);
+                player
+            }
+            None => Player::new(name.to_string()),
+        };
+
+        if is_player_win {
+            // Player Win
+            player.win = player.win.saturating_add(1);
+        } else if is_player_lose {
+            // Player Lose
+            player.lose = player.lose.saturating_add(1);
+        }
+        player
+    }
+}
+
+/// Rate every seat of a finished game against each other seat, from the ratings before the game.
+///
+/// A table with more than two seats counts as a head-to-head game against each opponent,
+/// and the rating change is averaged over the opponents.
+pub fn rate_game(players: &[SeatResult], ratings: &[u32], time: Timestamp) -> Vec<RatingChange> {
+    let mut changes = Vec::new();
+
+    for (index, player) in players.iter().enumerate() {
+        let mut delta = 0.0;
+        for (other, opponent) in players.iter().enumerate() {
+            if other == index {
+                continue;
+            }
+            let expected = 1.0 / (1.0 + 10f64.powf((ratings[other] as f64 - ratings[index] as f64) / 400.0));
+            delta += player.outcome.score_against(opponent.outcome) - expected;
+        }
+        let opponents = players.len().saturating_sub(1).max(1) as f64;
+        let change = (RATING_K_FACTOR * delta / opponents).round() as i32;
+
+        changes.push(RatingChange {
+            rating: ratings[index].saturating_add_signed(change),
+            change,
+            outcome: player.outcome,
+            time,
+        });
+    }
+    changes
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [RankKey]
+/// ------------------------------------------------------------------------------------------
+/// Index of a leaderboard entry, serialized so that the storage order is the ranking order:
+/// `score` descending, then `tiebreak` ascending, then `name`
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct RankKey {
+    pub score: u32,
+    pub tiebreak: u32,
+    pub name: String,
+}
+
+impl RankKey {
+    /// wins (desc), then losses (asc)
+    pub fn by_wins(player: &Player) -> Self {
+        RankKey { score: player.win, tiebreak: player.lose, name: player.name.clone() }
+    }
+
+    /// rating (desc), then games played (desc)
+    pub fn by_rating(player: &Player) -> Self {
+        RankKey { score: player.rating, tiebreak: u32::MAX - player.play, name: player.name.clone() }
+    }
+}
+
+impl CustomSerialize for RankKey {
+    fn to_custom_bytes(&self) -> Result<Vec<u8>, ViewError> {
+        let mut bytes = (u32::MAX - self.score).to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.tiebreak.to_be_bytes());
+        bytes.extend(bcs::to_bytes(&self.name)?);
+        Ok(bytes)
+    }
+
+    fn from_custom_bytes(short_key: &[u8]) -> Result<Self, ViewError> {
+        let (score, rest) = short_key.split_at(4);
+        let (tiebreak, name) = rest.split_at(4);
+        Ok(RankKey {
+            score: u32::MAX - u32::from_be_bytes(score.try_into().unwrap_or_default()),
+            tiebreak: u32::from_be_bytes(tiebreak.try_into().unwrap_or_default()),
+            name: bcs::from_bytes(name)?,
+        })
+    }
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [Season]
+/// ------------------------------------------------------------------------------------------
+/// Games played from `start` until `end` count toward the season standings
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct Season {
+    pub id: u32,
+    pub name: String,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// final standings are frozen in the season archive
+    pub archived: bool,
+}
+
+impl Season {
+    pub fn contains(&self, time: Timestamp) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    pub fn overlaps(&self, start: Timestamp, end: Timestamp) -> bool {
+        start < self.end && self.start < end
+    }
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [RatingChange]
+/// ------------------------------------------------------------------------------------------
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct RatingChange {
+    /// rating after the game
+    pub rating: u32,
+    pub change: i32,
+    pub outcome: SeatOutcome,
+    pub time: Timestamp,
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [VersionAnalytics]
+/// ------------------------------------------------------------------------------------------
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct VersionAnalytics {
+    pub v: String,
+    pub c: u32,
+}
+
+impl Default for VersionAnalytics {
+    fn default() -> Self {
+        Self { v: "".to_string(), c: 0 }
+    }
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [GidLeaderboard]
+/// ------------------------------------------------------------------------------------------
+/// One page of the gid leaderboard, players are stored by gid on the Leaderboard chain
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct GidLeaderboard {
+    pub gid: Vec<Player>,
+    pub count: u32,
+    /// players matching the query, across every page
+    pub total: u32,
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [PlayerProfile]
+/// ------------------------------------------------------------------------------------------
+/// Lifetime statistics of one player on the Leaderboard chain, keyed by gid and kept across resets
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct PlayerProfile {
+    pub gid: String,
+    /// every display name the player has finished a game with
+    pub names: Vec<String>,
+    pub play: u32,
+    pub win: u32,
+    pub lose: u32,
+    pub draw: u32,
+    pub blackjack: u32,
+    pub bust: u32,
+    pub stand_off: u32,
+    pub idle_forfeit: u32,
+    pub hits: u32,
+    /// wins in a row up to the last game played
+    pub win_streak: u32,
+    pub longest_win_streak: u32,
+    pub last_played: Timestamp,
+}
+
+impl PlayerProfile {
+    /// Profile after one more game, a new profile starts from the seat of its first game
+    pub fn record_game(entry: Option<PlayerProfile>, seat: &SeatResult, reason: GameEndReason, time: Timestamp) -> PlayerProfile {
+        let mut profile = entry.unwrap_or_else(|| PlayerProfile { gid: seat.gid.clone(), ..PlayerProfile::default() });
+
+        if !profile.names.contains(&seat.name) {
+            profile.names.push(seat.name.clone());
+        }
+        profile.play = profile.play.saturating_add(1);
+
+        match seat.outcome {
+            SeatOutcome::Win => {
+                profile.win = profile.win.saturating_add(1);
+                profile.win_streak = profile.win_streak.saturating_add(1);
+                profile.longest_win_streak = profile.longest_win_streak.max(profile.win_streak);
+            }
+            SeatOutcome::Lose => {
+                profile.lose = profile.lose.saturating_add(1);
+                profile.win_streak = 0;
+            }
+            SeatOutcome::Draw => {
+                profile.draw = profile.draw.saturating_add(1);
+                profile.win_streak = 0;
+            }
+        }
+
+        if seat.is_blackjack() {
+            profile.blackjack = profile.blackjack.saturating_add(1);
+        }
+        if seat.is_bust() {
+            profile.bust = profile.bust.saturating_add(1);
+        }
+        if reason == GameEndReason::StandOff {
+            profile.stand_off = profile.stand_off.saturating_add(1);
+        }
+        if seat.forfeit {
+            profile.idle_forfeit = profile.idle_forfeit.saturating_add(1);
+        }
+        profile.hits = profile.hits.saturating_add(seat.hits as u32);
+        profile.last_played = profile.last_played.max(time);
+        profile
+    }
+}
+
+/// ------------------------------------------------------------------------------------------
+/// [Matchmaking]
+/// ------------------------------------------------------------------------------------------
+/// Player waiting on the Room Status chain, `chain_id` receives the room to join
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct QueuedPlayer {
+    pub name: String,
+    pub gid: String,
+    pub chain_id: ChainId,
+    pub time: Timestamp,
+    /// leaderboard win rate in basis points when the player queued
+    pub win_rate: u32,
+    pub stake: Amount,
+}
+
+impl QueuedPlayer {
+    pub fn key(&self) -> MatchKey {
+        MatchKey { chain_id: self.chain_id, name: self.name.clone(), stake: self.stake }
+    }
+}
+
+/// `FindMatch` is not signed, so a name is only unique together with the chain that asked,
+/// players are only matched with players putting the same stake in the pot
+#[derive(Debug, Clone, Deserialize, Eq, Ord, PartialOrd, PartialEq, Serialize)]
+pub struct MatchKey {
+    pub chain_id: ChainId,
+    pub name: String,
+    pub stake: Amount,
+}
+scalar!(MatchKey);
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct MatchmakingSettings {
+    /// win rate difference in basis points accepted as soon as a player queues
+    pub band: u32,
+    /// basis points added to the band for every second a player waited
+    pub widening: u32,
+}
+
+impl Default for MatchmakingSettings {
+    fn default() -> Self {
+        MatchmakingSettings {
+            band: DEFAULT_MATCHMAKING_BAND,
+            widening: DEFAULT_MATCHMAKING_WIDENING,
+        }
+    }
+}
+
+impl MatchmakingSettings {
+    pub fn is_valid(&self) -> bool {
+        self.band <= MAX_WIN_RATE && self.widening <= MAX_WIN_RATE
+    }
+
+    /// band of a player who waited `waited` microseconds
+    pub fn band_after(&self, waited: u64) -> u32 {
+        let widened = self.widening as u64 * (waited / 1_000_000);
+        (self.band as u64).saturating_add(widened).min(MAX_WIN_RATE as u64) as u32
+    }
+}
+
+/// Pairing made by matchmaking, kept so players can audit how they were matched
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct MatchDecision {
+    pub time: Timestamp,
+    pub room: ChainId,
+    /// players sent to the room by this decision
+    pub players: Vec<String>,
+    pub win_rates: Vec<u32>,
+    /// win rate of the room, none when its seated players were not matched
+    pub room_win_rate: Option<u32>,
+    /// widest band of the players at the time of the decision
+    pub band: u32,
+}
+
+/// Seats of a room promised to matched players who haven't joined yet
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct RoomReservation {
+    pub players: Vec<String>,
+    pub time: Timestamp,
+}
+
+/// Room chain a player was matched to, kept on the chain that asked for the match
+#[derive(
+    Debug,
+    Clone,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    SimpleObject
+)]
+pub struct MatchAssignment {
+    pub room: ChainId,
+    pub time: Timestamp,
+}
+
 /// ------------------------------------------------------------------------------------------
 /// [PlayerStatus]
 /// ------------------------------------------------------------------------------------------
@@ -1,41 +1,24 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::{Mutex, OnceLock};
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use black_jack_chain::{ShoeCard, ShuffleSeed, ShuffleStep};
+use linera_sdk::base::CryptoHash;
 
-static RNG: OnceLock<Mutex<StdRng>> = OnceLock::new();
+pub fn shuffle_shoe(shoe: &mut [ShoeCard], seed: CryptoHash, game_number: u64) {
+    // every game gets its own seed, from the deck seed and game number
+    let shuffle_seed = CryptoHash::new(&ShuffleSeed { seed, game_number });
 
-pub fn custom_getrandom(buf: &mut [u8], seed: [u8; 32]) -> Result<(), getrandom::Error> {
-    RNG.get_or_init(|| Mutex::new(StdRng::from_seed(seed)))
-        .lock()
-        .expect("failed to get RNG lock")
-        .fill(buf);
-    Ok(())
-}
-
-pub fn generate_range(seed: [u8; 32], max_value: u8) -> u8 {
-    RNG.get_or_init(|| Mutex::new(StdRng::from_seed(seed)))
-        .lock()
-        .expect("failed to get RNG lock")
-        .gen_range(1..max_value)
-}
-
-pub fn truncate(s: &str, max_chars: usize) -> &str {
-    match s.char_indices().nth(max_chars) {
-        None => s,
-        Some((idx, _)) => &s[..idx],
+    // Fisher–Yates shuffle, every swap index comes straight from a hash so any client can replay it
+    for i in (1..shoe.len()).rev() {
+        let j = swap_index(shuffle_seed, i);
+        shoe.swap(i, j);
     }
 }
 
-pub fn random_index(timestamp: String, length: u8, id: String, seed: String) -> u8 {
-    // produce seed array using system time
-    let concatenated_timestamp = format!("{}{}{}{}{}", id, seed, timestamp, timestamp, timestamp);
-    let timestamp_str = truncate(concatenated_timestamp.as_str(), 32);
-    let mut seed_array = [0u8; 32];
-    seed_array = <[u8; 32]>::try_from(timestamp_str.as_bytes()).unwrap();
-
-    // get random index using provided seed
-    generate_range(seed_array, length)
+fn swap_index(seed: CryptoHash, step: usize) -> usize {
+    let hash = CryptoHash::new(&ShuffleStep { seed, step: step as u64 });
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_bytes()[..8]);
+    // the modulo bias is below 2^-50 for any shoe size
+    (u64::from_le_bytes(bytes) % (step as u64 + 1)) as usize
 }
-
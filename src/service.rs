@@ -1,21 +1,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
-mod constants;
 
-use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use async_graphql::{EmptySubscription, Executor, Schema};
+use async_graphql::{EmptySubscription, Schema};
 use async_graphql_derive::Object;
 use self::state::BlackJack;
 use linera_sdk::{
     base::WithServiceAbi,
-    views::{View},
+    views::{CustomSetView, MapView, View},
     Service, ServiceRuntime,
 };
-use linera_sdk::base::ChainId;
 use linera_sdk::graphql::GraphQLMutationRoot;
-use black_jack_chain::{CardOperation, GidLeaderboard, History, Insight, Leaderboard, PlayData, PlayerStatus, VersionAnalytics};
+use linera_sdk::base::{Amount, CryptoHash, Owner};
+use black_jack_chain::{commit_secret, CardOperation, GidLeaderboard, History, Insight, Leaderboard, GameLogEntry, MatchAssignment, MatchDecision, MatchKey, MatchmakingSettings, OutboxEntry, PlayData, Player, PlayerProfile, PlayerStatus, QueuedPlayer, RankKey, RatingChange, Season, ShoeSettings, SpectatorUpdate, Status, TimeoutSettings, VersionAnalytics};
 
 #[derive(Clone)]
 pub struct BlackJackService {
@@ -56,13 +54,7 @@ impl Service for BlackJackService {
 #[Object]
 impl BlackJackService {
     async fn get_insight(&self) -> Insight {
-        Insight {
-            id: ChainId::from_str("e4854ab09513d0e0b62497a5e190a074ff161c6c39e4dfa07dc5e2c0ee73d284")
-                .unwrap_or_else(|_| { panic!("unable to create insight chain id"); }),
-            game_state: self.state.game_state.get().clone(),
-            p_one: self.state.p1.get().clone(),
-            p_two: self.state.p2.get().clone(),
-        }
+        self.insight().await
     }
 
     async fn get_play_data(&self, player_id: String) -> PlayData {
@@ -78,32 +70,42 @@ impl BlackJackService {
     }
 
     async fn get_play_data_for_spectators(&self) -> PlayData {
-        let p_one = self.state.p1.get().clone();
-        let p_two = self.state.p2.get().clone();
+        self.spectator_play_data().await
+    }
 
-        let p1_play_data = self.state.play_data.get(&p_one.id).await
-            .unwrap_or(Some(PlayData::default()))
-            .unwrap_or(PlayData::default());
+    async fn get_game(&self, game_id: String) -> Option<History> {
+        self.state.games.get(&game_id).await.unwrap_or_else(|_| { panic!("unable to get game"); })
+    }
 
-        let p2_play_data = self.state.play_data.get(&p_two.id).await
-            .unwrap_or(Some(PlayData::default()))
-            .unwrap_or(PlayData::default());
+    async fn get_game_replay(&self, game_id: String) -> Vec<GameLogEntry> {
+        self.game_replay(&game_id).await
+    }
 
-        PlayData {
-            p_one_id: p_one.id,
-            p_two_id: p_two.id,
-            my_card: p2_play_data.opponent_card,
-            opponent_card: p1_play_data.opponent_card,
-            my_score: p2_play_data.opponent_score,
-            opponent_score: p1_play_data.opponent_score,
-            player_id_turn: p1_play_data.player_id_turn,
-            last_action: p1_play_data.last_action,
-            winner: p1_play_data.winner,
-            game_state: p1_play_data.game_state,
-            last_update: p1_play_data.last_update,
+    /// Room state for spectators, with the events of the current game from `since_event` on.
+    /// `since_event` only applies to `game_id`, every event is returned once a new game started.
+    ///
+    /// The service answers one query at a time, so clients subscribe to the node's new block
+    /// notifications for the room chain and call this query on every block instead of polling.
+    async fn get_spectator_update(&self, game_id: String, since_event: u32) -> SpectatorUpdate {
+        let current_game_id = self.state.game_id.get().clone();
+        let events: Vec<GameLogEntry> = self.game_replay(&current_game_id).await;
+        let next_event = events.len() as u32;
+        let since_event = if game_id == current_game_id { since_event } else { 0 };
+        let insight = self.insight().await;
+        let play_data = self.spectator_play_data().await;
+        SpectatorUpdate {
+            block_height: self.runtime.lock().unwrap_or_else(|_| { panic!("unable to lock service runtime"); }).next_block_height().0,
+            insight,
+            play_data,
+            events: events.into_iter().skip(since_event as usize).collect(),
+            next_event,
         }
     }
 
+    async fn get_commitment(&self, secret: String) -> CryptoHash {
+        commit_secret(&secret)
+    }
+
     async fn get_history(&self, limit: u32) -> Vec<History> {
         let history_count = self.state.history.count();
         if limit > history_count as u32 {
@@ -112,12 +114,86 @@ impl BlackJackService {
         self.state.history.read_back(limit as usize).await.unwrap_or_else(|_| { panic!("unable to read history"); })
     }
 
-    async fn get_leaderboard(&self) -> Leaderboard {
-        self.state.leaderboard.get().clone()
+    async fn get_leaderboard(&self, offset: u32, limit: u32, name: Option<String>) -> Leaderboard {
+        let (rank, total) = rank_page(&self.state.leaderboard_rank, &self.state.leaderboard, *self.state.leaderboard_players.get(), offset, limit, name).await;
+        Leaderboard { rank, count: *self.state.leaderboard_count.get(), total }
+    }
+
+    async fn get_rating_leaderboard(&self, offset: u32, limit: u32, name: Option<String>) -> Leaderboard {
+        let (rank, total) = rank_page(&self.state.rating_rank, &self.state.leaderboard, *self.state.leaderboard_players.get(), offset, limit, name).await;
+        Leaderboard { rank, count: *self.state.leaderboard_count.get(), total }
     }
 
-    async fn get_gid_leaderboard(&self) -> GidLeaderboard {
-        self.state.gid_leaderboard.get().clone()
+    async fn get_rating_history(&self, player_name: String) -> Vec<RatingChange> {
+        self.state.rating_history.get(&player_name).await
+            .unwrap_or_else(|_| { panic!("unable to get rating history"); })
+            .unwrap_or_default()
+    }
+
+    async fn get_seasons(&self) -> Vec<Season> {
+        let mut seasons = Vec::new();
+        for id in self.state.seasons.indices().await.unwrap_or_else(|_| { panic!("unable to read seasons"); }) {
+            let season = self.state.seasons.get(&id).await
+                .unwrap_or_else(|_| { panic!("unable to get season"); }).unwrap_or_else(|| { panic!("unable to get season"); });
+            seasons.push(season);
+        }
+        seasons
+    }
+
+    /// standings of the games played before `season_id` started
+    async fn get_offseason_standings(&self, season_id: u32, offset: u32, limit: u32) -> Leaderboard {
+        let mut standings = self.state.offseason_archive.get(&season_id).await
+            .unwrap_or_else(|_| { panic!("unable to get offseason standings"); })
+            .unwrap_or_default();
+        standings.rank = standings.rank.into_iter().skip(offset as usize).take(limit as usize).collect();
+        standings
+    }
+
+    async fn get_season_standings(&self, season_id: u32, offset: u32, limit: u32) -> Leaderboard {
+        // a season that ended is only archived by the next game result, until then the live leaderboard holds its standings
+        let season = self.state.seasons.get(&season_id).await
+            .unwrap_or_else(|_| { panic!("unable to get season"); });
+        if let Some(season) = season {
+            let now = self.runtime.lock().unwrap_or_else(|_| { panic!("unable to lock service runtime"); }).system_time();
+            let is_live = *self.state.active_season.get() == Some(season_id);
+            if is_live && !season.archived && now >= season.end {
+                let (rank, total) = rank_page(&self.state.leaderboard_rank, &self.state.leaderboard, *self.state.leaderboard_players.get(), offset, limit, None).await;
+                return Leaderboard { rank, count: *self.state.leaderboard_count.get(), total };
+            }
+        }
+
+        let mut standings = self.state.season_archive.get(&season_id).await
+            .unwrap_or_else(|_| { panic!("unable to get season standings"); })
+            .unwrap_or_default();
+        standings.rank = standings.rank.into_iter().skip(offset as usize).take(limit as usize).collect();
+        standings
+    }
+
+    async fn get_gid_leaderboard(&self, offset: u32, limit: u32, gid: Option<String>) -> GidLeaderboard {
+        let (gid, total) = rank_page(&self.state.gid_leaderboard_rank, &self.state.gid_leaderboard, *self.state.gid_leaderboard_players.get(), offset, limit, gid).await;
+        GidLeaderboard { gid, count: 0, total }
+    }
+
+    async fn get_player_profile(&self, gid: String) -> PlayerProfile {
+        self.state.player_profiles.get(&gid).await
+            .unwrap_or_else(|_| { panic!("unable to get player profile"); })
+            .unwrap_or_default()
+    }
+
+    async fn get_shoe_settings(&self) -> ShoeSettings {
+        *self.state.shoe_settings.get()
+    }
+
+    async fn get_timeout_settings(&self) -> TimeoutSettings {
+        *self.state.timeout_settings.get()
+    }
+
+    async fn get_shoe_remaining(&self) -> u32 {
+        self.state.decks.get().len() as u32
+    }
+
+    async fn get_admins(&self) -> Vec<Owner> {
+        self.state.admins.indices().await.unwrap_or_else(|_| { panic!("unable to read admins"); })
     }
 
     async fn get_game_room_status(&self) -> Vec<Insight> {
@@ -145,6 +221,47 @@ impl BlackJackService {
     }
 
 
+    async fn get_pending_results(&self) -> Vec<History> {
+        let mut pending = Vec::new();
+        for result in self.state.pending_results.elements().await.unwrap_or_else(|_| { panic!("unable to read pending results"); }) {
+            // discarded results wait in the queue until it's drained
+            if self.state.pending_game_ids.contains(&result.game_id).await.unwrap_or(false) {
+                pending.push(result);
+            }
+        }
+        pending
+    }
+
+    async fn get_outbox(&self) -> Vec<OutboxEntry> {
+        self.state.outbox.elements().await.unwrap_or_else(|_| { panic!("unable to read outbox"); })
+    }
+
+    async fn get_match_queue(&self) -> Vec<QueuedPlayer> {
+        let mut queue = Vec::new();
+        for key in self.state.match_queue.indices().await.unwrap_or_else(|_| { panic!("unable to read match queue"); }) {
+            let queued = self.state.match_queue.get(&key).await
+                .unwrap_or_else(|_| { panic!("unable to get queued player"); }).unwrap_or_else(|| { panic!("unable to get queued player"); });
+            queue.push(queued);
+        }
+        queue.sort_by(|a, b| a.time.cmp(&b.time).then(a.name.cmp(&b.name)));
+        queue
+    }
+
+    async fn get_matchmaking_settings(&self) -> MatchmakingSettings {
+        *self.state.matchmaking_settings.get()
+    }
+
+    async fn get_match_log(&self, limit: u32) -> Vec<MatchDecision> {
+        let log_count = self.state.match_log.count();
+        self.state.match_log.read_back(log_count.min(limit as usize)).await.unwrap_or_else(|_| { panic!("unable to read match log"); })
+    }
+
+    async fn get_match(&self, player_name: String, stake: Option<Amount>) -> Option<MatchAssignment> {
+        let chain_id = self.runtime.lock().unwrap_or_else(|_| { panic!("unable to lock service runtime"); }).chain_id();
+        let stake = stake.unwrap_or(Amount::ZERO);
+        self.state.matches.get(&MatchKey { chain_id, name: player_name, stake }).await.unwrap_or_else(|_| { panic!("unable to get match"); })
+    }
+
     async fn get_player_status(&self, name: String) -> PlayerStatus {
         if self.state.player_status.contains_key(&name).await.unwrap_or(false) {
             return self.state.player_status.get(&name).await
@@ -154,3 +271,121 @@ impl BlackJackService {
         PlayerStatus::default()
     }
 }
+
+/// ------------------------------------------------------------------------------------------
+impl BlackJackService {
+    async fn insight(&self) -> Insight {
+        let seats = self.state.seated_players().await;
+        Insight {
+            id: self.runtime.lock().unwrap_or_else(|_| { panic!("unable to lock service runtime"); }).chain_id(),
+            game_id: self.state.game_id.get().clone(),
+            game_mode: *self.state.game_mode.get(),
+            game_state: self.state.game_state.get().clone(),
+            seat_count: *self.state.seat_count.get(),
+            seats,
+        }
+    }
+
+    async fn spectator_play_data(&self) -> PlayData {
+        let seats = self.state.seated_players().await;
+        let p_one = seats.first().cloned().unwrap_or_default();
+        let p_two = seats.get(1).cloned().unwrap_or_default();
+
+        let p1_play_data = self.state.play_data.get(&p_one.id).await
+            .unwrap_or(Some(PlayData::default()))
+            .unwrap_or(PlayData::default());
+
+        // table hides every hole card until the game finish
+        let table = self.state.table().await;
+        let seat_one = table.first().cloned().unwrap_or_default();
+        let seat_two = table.get(1).cloned().unwrap_or_default();
+
+        let play_data = PlayData {
+            game_id: p1_play_data.game_id,
+            game_number: p1_play_data.game_number,
+            p_one_id: p_one.id,
+            p_two_id: p_two.id,
+            my_card: seat_one.cards,
+            opponent_card: seat_two.cards,
+            my_score: seat_one.score,
+            opponent_score: seat_two.score,
+            my_hands: vec![],
+            active_hand: p1_play_data.active_hand,
+            player_id_turn: p1_play_data.player_id_turn,
+            last_action: p1_play_data.last_action,
+            winner: p1_play_data.winner,
+            game_state: p1_play_data.game_state,
+            last_update: p1_play_data.last_update,
+            deck_seed: p1_play_data.deck_seed,
+            table,
+        };
+
+        // hole card is face down for spectators until the game finish, same as the table
+        if play_data.game_state == Status::Finish {
+            return play_data;
+        }
+        play_data.face_down()
+    }
+
+    async fn game_replay(&self, game_id: &String) -> Vec<GameLogEntry> {
+        let log = self.state.game_log.get(game_id).await
+            .unwrap_or_else(|_| { panic!("unable to get game log"); })
+            .unwrap_or_default();
+
+        // hole cards of the game in progress stay face down until it finish
+        let in_progress = *game_id == *self.state.game_id.get() && self.state.game_state.get().status != Status::Finish;
+        if in_progress {
+            return log.into_iter().map(|entry| GameLogEntry { event: entry.event.face_down(), ..entry }).collect();
+        }
+        log
+    }
+}
+
+/// ------------------------------------------------------------------------------------------
+async fn rank_page(
+    rank: &CustomSetView<RankKey>,
+    entries: &MapView<String, Player>,
+    entry_count: u32,
+    offset: u32,
+    limit: u32,
+    filter: Option<String>,
+) -> (Vec<Player>, u32) {
+    let (keys, total) = match filter {
+        // rank index is already stored in ranking order, the walk stops at the end of the page
+        None => {
+            let mut keys = Vec::new();
+            let mut index = 0;
+            rank.for_each_index_while(|key| {
+                if index >= offset.saturating_add(limit) {
+                    return Ok(false);
+                }
+                if index >= offset {
+                    keys.push(key);
+                }
+                index += 1;
+                Ok(true)
+            }).await.unwrap_or_else(|_| { panic!("unable to read leaderboard rank"); });
+            (keys, entry_count)
+        }
+        // a name filter has to look at every key to count the matches
+        Some(name) => {
+            let name = name.to_lowercase();
+            let keys: Vec<RankKey> = rank.indices().await
+                .unwrap_or_else(|_| { panic!("unable to read leaderboard rank"); })
+                .into_iter()
+                .filter(|key| key.name.to_lowercase().contains(&name))
+                .collect();
+            let total = keys.len() as u32;
+            (keys.into_iter().skip(offset as usize).take(limit as usize).collect(), total)
+        }
+    };
+
+    // only the entries of the page are loaded
+    let mut players = Vec::new();
+    for key in keys.iter() {
+        let player = entries.get(&key.name).await
+            .unwrap_or_else(|_| { panic!("unable to get leaderboard entry"); }).unwrap_or_else(|| { panic!("unable to get leaderboard entry"); });
+        players.push(player);
+    }
+    (players, total)
+}
@@ -1,24 +1,122 @@
-use linera_sdk::base::ChainId;
-use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext, QueueView};
-use black_jack_chain::{Player, GameState, PlayData, History, Insight, VersionAnalytics, Leaderboard, GidLeaderboard, PlayerStatus};
+use linera_sdk::base::{Amount, ChainId, CryptoHash, Owner};
+use linera_sdk::views::{linera_views, CustomSetView, MapView, RegisterView, RootView, SetView, ViewStorageContext, QueueView};
+use black_jack_chain::{Hand, Status, TableSeat, ShoeCard, ShoeSettings, TimeoutSettings, Player, GameMode, GameState, PlayData, History, Insight, VersionAnalytics, GameLogEntry, MatchAssignment, MatchDecision, MatchKey, MatchmakingSettings, OutboxEntry, QueuedPlayer, RoomReservation, Leaderboard, RankKey, Season, RatingChange, PlayerStatus, PlayerProfile};
 
 #[derive(RootView, async_graphql::SimpleObject)]
 #[view(context = "ViewStorageContext")]
 pub struct BlackJack {
     pub game_state: RegisterView<GameState>,
-    pub p1: RegisterView<Player>,
-    pub p2: RegisterView<Player>,
-    pub decks: RegisterView<Vec<u8>>,
+    pub game_mode: RegisterView<GameMode>,
+    pub seat_count: RegisterView<u8>,
+    pub seats: MapView<u8, Player>,
+    /// stakes the room chain holds for the seated players, block fees are charged to the same chain balance
+    pub escrow: RegisterView<Amount>,
+    pub consecutive_stands: RegisterView<u8>,
+    pub decks: RegisterView<Vec<ShoeCard>>,
+    /// cards drawn from the shoe in the current game, left out when the shoe is refilled mid-game
+    pub dealt_cards: RegisterView<Vec<ShoeCard>>,
+    pub shoe_settings: RegisterView<ShoeSettings>,
+    pub timeout_settings: RegisterView<TimeoutSettings>,
+    pub table_settings_requested: RegisterView<bool>,
+    /// settings received from Leaderboard chain, applied when the next table opens
+    pub pending_shoe_settings: RegisterView<Option<ShoeSettings>>,
+    pub pending_timeout_settings: RegisterView<Option<TimeoutSettings>>,
+    pub commitments: MapView<String, CryptoHash>,
+    pub reveals: MapView<String, String>,
+    pub deck_seed: RegisterView<Option<CryptoHash>>,
+    pub game_count: RegisterView<u64>,
+    pub game_id: RegisterView<String>,
     pub play_data: MapView<String, PlayData>,
+    /// append-only log of every game played on the room chain, keyed by game id
+    pub game_log: MapView<String, Vec<GameLogEntry>>,
+    /// game results and room updates rejected by their destination, resent on demand
+    pub outbox: QueueView<OutboxEntry>,
     // leaderboard chain
-    pub leaderboard: RegisterView<Leaderboard>,
-    pub gid_leaderboard: RegisterView<GidLeaderboard>,
+    pub leaderboard: MapView<String, Player>,
+    pub leaderboard_rank: CustomSetView<RankKey>,
+    pub rating_rank: CustomSetView<RankKey>,
+    pub leaderboard_count: RegisterView<u32>,
+    /// entries of the live leaderboard, so a page doesn't need to count the rank index
+    pub leaderboard_players: RegisterView<u32>,
+    pub gid_leaderboard: MapView<String, Player>,
+    pub gid_leaderboard_rank: CustomSetView<RankKey>,
+    pub gid_leaderboard_players: RegisterView<u32>,
     pub history: QueueView<History>,
+    pub games: MapView<String, History>,
+    /// results received while the leaderboard is stopped, applied when it starts again
+    pub pending_results: QueueView<History>,
+    /// game ids of `pending_results` that are still to be applied
+    pub pending_game_ids: SetView<String>,
+    pub rating_history: MapView<String, Vec<RatingChange>>,
+    /// lifetime statistics keyed by gid, not cleared by a leaderboard reset
+    pub player_profiles: MapView<String, PlayerProfile>,
+    pub seasons: MapView<u32, Season>,
+    pub season_count: RegisterView<u32>,
+    /// season the live leaderboard belongs to, none between seasons
+    pub active_season: RegisterView<Option<u32>>,
+    pub season_archive: MapView<u32, Leaderboard>,
+    /// standings played before a season started, keyed by the id of that season
+    pub offseason_archive: MapView<u32, Leaderboard>,
     pub leaderboard_on: RegisterView<bool>,
+    pub admins: SetView<Owner>,
     // room status chain
     pub room_status: MapView<ChainId, Insight>,
+    pub free_rooms: SetView<ChainId>,
+    pub reserved_rooms: MapView<ChainId, RoomReservation>,
+    pub match_queue: MapView<MatchKey, QueuedPlayer>,
+    /// average win rate of the players matched into a room
+    pub room_win_rate: MapView<ChainId, u32>,
+    pub matchmaking_settings: RegisterView<MatchmakingSettings>,
+    pub match_log: QueueView<MatchDecision>,
+    /// last house secret drawn, every new one is chained from it
+    pub house_entropy: RegisterView<Option<CryptoHash>>,
+    // any chain that asked for a match
+    pub matches: MapView<MatchKey, MatchAssignment>,
     // analytics chain
     pub analytics: MapView<String, VersionAnalytics>,
     // player status chain
     pub player_status: MapView<String, PlayerStatus>,
 }
+
+impl BlackJack {
+    pub async fn seated_players(&self) -> Vec<Player> {
+        let mut players = Vec::new();
+        for seat in self.seats.indices().await.unwrap_or_else(|_| { panic!("unable to read seats"); }) {
+            let player = self.seats.get(&seat).await
+                .unwrap_or_else(|_| { panic!("unable to get seat {:?}", seat); }).unwrap_or_else(|| { panic!("unable to get seat {:?}", seat); });
+            players.push(player);
+        }
+        players
+    }
+
+    pub async fn table(&self) -> Vec<TableSeat> {
+        let finished = self.game_state.get().status == Status::Finish;
+        let mut table = Vec::new();
+
+        for (seat, player) in self.seated_players().await.into_iter().enumerate() {
+            let play_data = self.play_data.get(&player.id).await
+                .unwrap_or_else(|_| { panic!("unable to get play data"); }).unwrap_or_default();
+            let commit = self.commitments.get(&player.id).await
+                .unwrap_or_else(|_| { panic!("unable to get commitment"); });
+            let reveal = self.reveals.get(&player.id).await
+                .unwrap_or_else(|_| { panic!("unable to get reveal"); });
+
+            // hole cards and secrets are public once the game finish
+            let hand = Hand::new(play_data.my_card);
+            let hand = if finished { hand } else { hand.face_up() };
+
+            table.push(TableSeat {
+                seat: seat as u8,
+                player_id: player.id,
+                name: player.name,
+                stake: player.stake,
+                score: hand.value(),
+                cards: hand.cards,
+                commit,
+                revealed: reveal.is_some(),
+                reveal: if finished { reveal.unwrap_or_default() } else { String::from("") },
+            });
+        }
+        table
+    }
+}